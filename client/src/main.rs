@@ -10,8 +10,13 @@ use futures::StreamExt;
 use scene::GameData;
 use scene::LobbyData;
 use scene::Scene;
+use scene::{
+    LookDestination, Looking, Searching, arranged_notice, combat_notice, looked_notice,
+    searched_notice,
+};
 use shared::DeckType;
 use shared::LocalDeckTop;
+use shared::LocalGameResult;
 use shared::RelSide;
 use shrek_deck::GetCardInfo;
 use std::sync::LazyLock;
@@ -73,13 +78,13 @@ enum CommunicationError {
 enum ChannelError {
     LocalToNetworkClosed,
     NetworkToLocalClosed,
-    NetworkToServerError(Error),
+    NetworkToServerError(#[allow(dead_code)] Error),
 }
 
 #[derive(Debug)]
 enum NetRuntimeError {
     TokioBuildError,
-    ChannelError(ChannelError),
+    ChannelError(#[allow(dead_code)] ChannelError),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -213,17 +218,57 @@ async fn main() {
     }
 }
 
-fn process_server_error(msg: ServerErr, current_scene: &mut Scene) {
+fn process_server_error(msg: ServerErr, _current_scene: &mut Scene) {
     match msg {
-        ServerErr::RoomDoesntExist(string) => println!("Room doesn't exist"),
+        ServerErr::RoomDoesntExist(_) => println!("Room doesn't exist"),
         ServerErr::NotInGame { action } => println!("Not in game, but tried to {action}"),
         ServerErr::NotInSide => println!("Player is not currently playing"),
         ServerErr::NoPlayerInSide(side) => println!("Player is not in {side:?}"),
         ServerErr::NoCardIn(place_from) => println!("No card in {place_from:?}"),
+        ServerErr::NoCountersOn(target) => println!("Can't put counters on {target:?}"),
+        ServerErr::TokenOffBoard => println!("Tokens can only go on the board"),
+        ServerErr::NothingToAttachTo(side, space) => {
+            println!("There's nothing to attach to in {side:?} {space:?}")
+        }
+        ServerErr::NoSuchSpace(side, space) => {
+            println!("{side:?} row has no space {}", space.0 + 1)
+        }
+        ServerErr::BadSpaceCount(spaces) => println!("Rows can't have {spaces} spaces"),
+        ServerErr::SpaceInUse(side, space) => {
+            println!("{side:?} space {} still has a card in it", space.0 + 1)
+        }
+        ServerErr::CannotCopyHidden(from) => println!("You don't know the card in {from:?}"),
+        ServerErr::CannotPeek(from) => println!("You can't peek at the card in {from:?}"),
+        ServerErr::CannotReveal(from) => println!("You can't reveal the card in {from:?}"),
         ServerErr::SideOccupied(side) => println!("{side:?} is already occupied"),
-        ServerErr::AlreadyInGame { action } => println!("Already in game"),
+        ServerErr::AlreadyInGame { .. } => println!("Already in game"),
         ServerErr::GameIsFull => println!("Game is full"),
         ServerErr::RoomAlreadyExist => println!("Room alrady exist"),
+        ServerErr::GameIsOver => println!("Game is already over"),
+        ServerErr::GameNotStarted => println!("The game hasn't started yet"),
+        ServerErr::GameNotOver => println!("Game is not over yet"),
+        ServerErr::NoDrawOffered => println!("No draw was offered"),
+        ServerErr::NoStartRulesProposed => println!("Your opponent didn't propose any start rules"),
+        ServerErr::GameInProgress => println!("Finish the current game first"),
+        ServerErr::MatchTooShort => println!("Matches are at least one game long"),
+        ServerErr::MatchUnderway => println!("The match length can't change once a game is played"),
+        ServerErr::NotMulliganing => println!("Not deciding on an opening hand"),
+        ServerErr::StillMulliganing => println!("Everyone has to keep their hand first"),
+        ServerErr::BadRoll => println!("Can't roll that"),
+        ServerErr::NoCardsIn(side, zone) => println!("No cards in {side:?} {zone:?}"),
+        ServerErr::NotLooking => println!("Not looking at any deck"),
+        ServerErr::BadArrangement => println!("Every card has to go somewhere exactly once"),
+        ServerErr::DeckChangedSinceLook => println!("The deck changed since you looked at it"),
+        ServerErr::LookAtWholeDeck => println!("That's the whole deck, search it instead"),
+        ServerErr::SearchDenied => println!("Your opponent didn't let you search their deck"),
+        ServerErr::NoSearchRequest => println!("Nobody asked to search your deck"),
+        ServerErr::NotSearching => println!("Not searching any deck"),
+        ServerErr::NotYourTurn => println!("It's not your turn"),
+        ServerErr::NoPriority => println!("Your opponent has priority"),
+        ServerErr::NotCombatStep => println!("Attacks can only be declared during combat"),
+        ServerErr::NoCombat => println!("Nobody is attacking"),
+        ServerErr::BadAttack => println!("Can't attack like that"),
+        ServerErr::BadBlock => println!("Can't block like that"),
     }
 }
 
@@ -256,13 +301,79 @@ fn process_server_message(
                 RelSide::Same => board_state.local_state.timeline = vec,
                 RelSide::Other => board_state.distant_state.timeline = vec,
             },
-            ServerMsg::BeginSearch(vec) => match current_scene {
-                Scene::LobbySelect(lobby_data) => todo!(),
-                Scene::Game(game_data) => game_data.seaching = vec,
+            ServerMsg::BeginSearch { side, deck, cards } => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => {
+                    game_data.searching = Some(Searching { side, deck, cards })
+                }
+            },
+            ServerMsg::Searched {
+                by,
+                side,
+                deck,
+                shuffled,
+            } => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => {
+                    game_data.notice = Some(searched_notice(by, side, deck, shuffled))
+                }
             },
             ServerMsg::UpdateState(new_state) => {
                 *board_state = *new_state;
             }
+            ServerMsg::GameOver { winner, reason } => {
+                board_state.result = Some(LocalGameResult { winner, reason });
+            }
+            ServerMsg::History(entry) => board_state.history.push(entry),
+            ServerMsg::Revealed { by, cards } => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => game_data.revealed = Some((by, cards)),
+            },
+            ServerMsg::Clocks(clocks) => board_state.turn.clocks = Some(clocks),
+            ServerMsg::OutOfTime(side) => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => {
+                    game_data.notice = Some(
+                        match side {
+                            RelSide::Same => "You ran out of time",
+                            RelSide::Other => "Your opponent ran out of time",
+                        }
+                        .to_owned(),
+                    )
+                }
+            },
+            ServerMsg::CombatResolved(hits) => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => game_data.notice = Some(combat_notice(&hits)),
+            },
+            ServerMsg::LookAtTop { side, deck, cards } => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => {
+                    game_data.looking = Some(Looking {
+                        side,
+                        deck,
+                        cards: cards
+                            .into_iter()
+                            .map(|x| (x, LookDestination::Top))
+                            .collect(),
+                    })
+                }
+            },
+            ServerMsg::LookedAtTop { by, side, deck, n } => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => game_data.notice = Some(looked_notice(by, side, deck, n)),
+            },
+            ServerMsg::TopArranged {
+                by,
+                side,
+                deck,
+                counts,
+            } => match current_scene {
+                Scene::LobbySelect(_) => todo!(),
+                Scene::Game(game_data) => {
+                    game_data.notice = Some(arranged_notice(by, side, deck, counts))
+                }
+            },
             ServerMsg::JoinedRoom(..) => panic!("??"),
             ServerMsg::RoomCreated => panic!("??"),
         }
@@ -273,22 +384,46 @@ fn process_server_message(
         ServerMsg::UpdateSpaces { .. } => panic!("??"),
         ServerMsg::UpdateDiscard(..) => panic!("??"),
         ServerMsg::UpdateTimeline(..) => panic!("??"),
-        ServerMsg::BeginSearch(..) => panic!("??"),
+        ServerMsg::BeginSearch { .. } => panic!("??"),
+        ServerMsg::Searched { .. } => panic!("??"),
         ServerMsg::UpdateState(..) => panic!("??"),
+        ServerMsg::GameOver { .. } => panic!("??"),
+        ServerMsg::History(..) => panic!("??"),
+        ServerMsg::LookAtTop { .. } => panic!("??"),
+        ServerMsg::LookedAtTop { .. } => panic!("??"),
+        ServerMsg::TopArranged { .. } => panic!("??"),
+        ServerMsg::Revealed { .. } => panic!("??"),
+        ServerMsg::CombatResolved(..) => panic!("??"),
+        ServerMsg::Clocks(..) => panic!("??"),
+        ServerMsg::OutOfTime(..) => panic!("??"),
         ServerMsg::RoomCreated => (),
         ServerMsg::JoinedRoom(state) => {
             to_server.send(ClientMsg::PlayAs).unwrap();
-            *current_scene = Scene::Game(GameData {
+            *current_scene = Scene::Game(Box::new(GameData {
                 state: *state,
                 editing_deck: false,
                 deck: DeckType::Main,
                 marrow_main: String::new(),
                 marrow_blood: String::new(),
                 marrow_error: String::new(),
-                seaching: vec![],
+                searching: None,
+                search_filter: String::new(),
                 creating: String::new(),
                 viewing_aside: false,
-            })
+                viewing_discard: None,
+                dice_count: 1,
+                dice_sides: 6,
+                viewing_history: false,
+                deck_count: 3,
+                looking: None,
+                notice: None,
+                revealed: None,
+                attack_plan: vec![],
+                block_plan: vec![],
+                hook_counter: String::new(),
+                bulk_counter: String::new(),
+                bulk_value: 0,
+            }))
         }
     }
 }
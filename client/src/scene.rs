@@ -1,7 +1,10 @@
 mod game;
 mod lobby;
-pub use game::GameData;
 use game::draw_game;
+pub use game::{
+    GameData, LookDestination, Looking, Searching, arranged_notice, combat_notice, looked_notice,
+    searched_notice,
+};
 pub use lobby::LobbyData;
 use lobby::draw_lobby_select;
 use shared::ClientMsg;
@@ -10,7 +13,7 @@ use tokio::sync::mpsc::UnboundedSender;
 #[derive(Debug, Clone)]
 pub enum Scene {
     LobbySelect(LobbyData),
-    Game(GameData),
+    Game(Box<GameData>),
 }
 
 impl Scene {
@@ -1,14 +1,18 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
 use egui_macroquad::egui::{
-    self, Align, Color32, Context, CursorIcon, DragAndDrop, Frame, Id, ImageButton, InnerResponse,
-    LayerId, Layout, Order, Response, Sense, Style, Ui, UiBuilder, Vec2, Widget,
+    self, Align2, Color32, Context, CursorIcon, DragAndDrop, FontId, Frame, Id, ImageButton,
+    InnerResponse, LayerId, Layout, Order, Response, Sense, Style, Ui, UiBuilder, Vec2, Widget,
     emath::TSTransform,
 };
 use macroquad::input::{KeyCode, is_key_down};
 use shared::{
-    ClientMsg, DeckType, Hidden, LocalCard, LocalState, NamedCardId, PlaceFrom, RelSide, Space,
-    TurnStep,
+    ArrangedCounts, Attack, AttackTarget, Block, ClientMsg, ClockRules, CounterTarget,
+    DEFAULT_SPACES, DEFENSE, DeckType, HEALTH, Hidden, LocalCard, LocalDeckTop, LocalHistoryEntry,
+    LocalHistoryEvent, LocalHit, LocalHitTarget, LocalState, MAX_DICE, MAX_SPACES, MulliganRule,
+    NamedCardId, POWER, PlaceFrom, PlaceTo, RelSide, RevealTarget, Space, Status, StepAction,
+    StepHook, TimeoutRule, TopArrangement, TurnStep, Zone,
 };
 use shrek_deck::parser::parse_line;
 use tokio::sync::mpsc::UnboundedSender;
@@ -25,13 +29,87 @@ pub struct GameData {
     pub marrow_main: String,
     pub marrow_blood: String,
     pub marrow_error: String,
-    pub seaching: Vec<NamedCardId>,
+    pub searching: Option<Searching>,
+    pub search_filter: String,
     pub creating: String,
     pub viewing_aside: bool,
+    pub viewing_discard: Option<RelSide>,
+    pub dice_count: u32,
+    pub dice_sides: u32,
+    pub viewing_history: bool,
+    pub deck_count: usize,
+    pub looking: Option<Looking>,
+    /// The last thing the other player did that they had to tell us about.
+    pub notice: Option<String>,
+    /// Cards someone showed us, until we close the popup.
+    pub revealed: Option<(RelSide, Vec<NamedCardId>)>,
+    /// What each of our spaces attacks, before the attack is declared.
+    pub attack_plan: Vec<Option<AttackTarget>>,
+    /// Which attacker each of our spaces blocks, before the blocks are declared.
+    pub block_plan: Vec<Option<Space>>,
+    /// The counter typed in for a new step action.
+    pub hook_counter: String,
+    /// The counter and value to set on many creatures at once.
+    pub bulk_counter: String,
+    pub bulk_value: isize,
+}
+
+/// A deck the player is searching.
+#[derive(Debug, Clone)]
+pub struct Searching {
+    pub side: RelSide,
+    pub deck: DeckType,
+    pub cards: Vec<NamedCardId>,
+}
+
+/// The top of a deck the player is looking at, and where each card is going.
+#[derive(Debug, Clone)]
+pub struct Looking {
+    pub side: RelSide,
+    pub deck: DeckType,
+    /// Top card first.
+    pub cards: Vec<(NamedCardId, LookDestination)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookDestination {
+    Top,
+    Bottom,
+    Hand,
+    Discard,
+}
+
+impl LookDestination {
+    fn get_name(self) -> &'static str {
+        match self {
+            LookDestination::Top => "Top",
+            LookDestination::Bottom => "Bottom",
+            LookDestination::Hand => "Hand",
+            LookDestination::Discard => "Discard",
+        }
+    }
+}
+
+impl Looking {
+    fn arrangement(&self) -> TopArrangement {
+        let mut arrangement = TopArrangement::default();
+        for (card, destination) in &self.cards {
+            let list = match destination {
+                LookDestination::Top => &mut arrangement.top,
+                LookDestination::Bottom => &mut arrangement.bottom,
+                LookDestination::Hand => &mut arrangement.hand,
+                LookDestination::Discard => &mut arrangement.discard,
+            };
+            list.push(card.id);
+        }
+        arrangement
+    }
 }
 
 pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameData) {
     egui_macroquad::ui(|ctx| {
+        let spaces = data.state.local_row.len();
+        ctx.data_mut(|x| x.insert_temp(Id::new(ROW_LEN), spaces));
         egui::TopBottomPanel::top("topbar").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.menu_button("Deck", |ui| {
@@ -49,6 +127,97 @@ pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameDa
                         data.marrow_error = String::new();
                     }
                 });
+                ui.menu_button("Game", |ui| {
+                    let playing = data.state.started && data.state.result.is_none();
+                    if ui
+                        .add_enabled(!playing, egui::Button::new("Start game"))
+                        .clicked()
+                    {
+                        to_server.send(ClientMsg::StartGame).unwrap();
+                    }
+                    if ui
+                        .add_enabled(playing, egui::Button::new("Concede"))
+                        .clicked()
+                    {
+                        to_server.send(ClientMsg::Concede).unwrap();
+                    }
+                    let offered = data.state.draw_offer == Some(RelSide::Same);
+                    if ui
+                        .add_enabled(playing && !offered, egui::Button::new("Offer draw"))
+                        .clicked()
+                    {
+                        to_server.send(ClientMsg::OfferDraw).unwrap();
+                    }
+                    ui.separator();
+                    let underway = data.state.score.games_played() > 0;
+                    for best_of in [1, 3, 5] {
+                        let current = data.state.score.best_of == best_of;
+                        let radio = egui::RadioButton::new(current, format!("Best of {best_of}"));
+                        if ui.add_enabled(!underway, radio).clicked() {
+                            to_server.send(ClientMsg::SetMatchLength(best_of)).unwrap();
+                        }
+                    }
+                    ui.separator();
+                    // Edit our own proposal if there is one, so it doesn't snap back
+                    let current = match data.state.proposed_start_rules {
+                        Some((RelSide::Same, rules)) => rules,
+                        _ => data.state.start_rules,
+                    };
+                    let mut rules = current;
+                    ui.add(egui::Slider::new(&mut rules.main_cards, 0..=10).text("Main cards"));
+                    ui.add(egui::Slider::new(&mut rules.blood_cards, 0..=10).text("Blood cards"));
+                    for rule in [
+                        MulliganRule::Disabled,
+                        MulliganRule::Free,
+                        MulliganRule::OneFewer,
+                    ] {
+                        ui.radio_value(&mut rules.mulligan, rule, rule.get_name());
+                    }
+                    if rules != current {
+                        to_server.send(ClientMsg::SetStartRules(rules)).unwrap();
+                    }
+                    match data.state.proposed_start_rules {
+                        Some((RelSide::Same, _)) => {
+                            ui.label("Waiting for your opponent to accept");
+                        }
+                        Some((RelSide::Other, proposed)) => {
+                            ui.label(format!(
+                                "Your opponent proposes {} main and {} blood cards, {}",
+                                proposed.main_cards,
+                                proposed.blood_cards,
+                                proposed.mulligan.get_name().to_lowercase()
+                            ));
+                            if ui.button("Accept").clicked() {
+                                to_server.send(ClientMsg::AcceptStartRules).unwrap();
+                            }
+                        }
+                        None => {}
+                    }
+                    ui.separator();
+                    let mut strict = data.state.strict;
+                    let checkbox = egui::Checkbox::new(&mut strict, "Strict turns");
+                    if ui.add_enabled(!playing, checkbox).changed() {
+                        to_server.send(ClientMsg::SetStrict(strict)).unwrap();
+                    }
+                    ui.separator();
+                    let mut spaces = data.state.local_row.len();
+                    ui.add(egui::Slider::new(&mut spaces, 1..=MAX_SPACES).text("Spaces"));
+                    if spaces != data.state.local_row.len() {
+                        to_server.send(ClientMsg::SetSpaces(spaces)).unwrap();
+                    }
+                    ui.separator();
+                    ui.add_enabled_ui(!playing, |ui| clock_settings(ui, data, to_server));
+                });
+                ui.menu_button("Step actions", |ui| step_actions(ui, data, to_server));
+                ui.menu_button("Counters", |ui| bulk_counters(ui, data, to_server));
+                let score = data.state.score;
+                ui.label(format!(
+                    "Best of {}: {} - {}",
+                    score.best_of, score.local_wins, score.distant_wins
+                ));
+                if score.draws > 0 {
+                    ui.label(format!("({} drawn)", score.draws));
+                }
             });
         });
         egui::SidePanel::left("turntracker")
@@ -57,6 +226,11 @@ pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameDa
             .show(ctx, |ui| {
                 let avh = ui.available_height();
                 ui.add_space((avh - 64. * 5.) / 2.);
+                // Strict rooms only let the active player move the turn along, and only while
+                // they hold priority
+                let strict = data.state.strict;
+                let can_act = !strict || data.state.priority == RelSide::Same;
+                let enabled = can_act && (!strict || data.state.turn.whose == RelSide::Same);
                 ui.vertical_centered_justified(|ui| {
                     for step in [
                         TurnStep::Start,
@@ -70,9 +244,25 @@ pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameDa
                             data.state.turn.whose,
                             data.state.turn.step,
                             step,
+                            enabled,
                             to_server,
                         );
                     }
+                    if strict {
+                        ui.label(match data.state.priority {
+                            RelSide::Same => "Your priority",
+                            RelSide::Other => "Their priority",
+                        });
+                        if ui.add_enabled(can_act, egui::Button::new("Pass")).clicked() {
+                            to_server.send(ClientMsg::PassPriority).unwrap();
+                        }
+                    }
+                    if let Some(clocks) = data.state.turn.clocks {
+                        ui.separator();
+                        let running = clocks.running;
+                        clock(ui, clocks.distant, running == Some(RelSide::Other));
+                        clock(ui, clocks.local, running == Some(RelSide::Same));
+                    }
                 })
             });
         egui::TopBottomPanel::bottom("bottombar").show(ctx, |ui| {
@@ -86,14 +276,34 @@ pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameDa
                             .unwrap();
                     }
                 }
-                if ui.button("Aside").clicked() {
+                let token = data.creating.trim().to_owned();
+                ui.add_enabled_ui(!token.is_empty(), |ui| {
+                    ui.menu_button("Create Token", |ui| {
+                        board_places(ui, |to| {
+                            to_server
+                                .send(ClientMsg::CreateToken(token.clone(), to))
+                                .unwrap();
+                        })
+                    });
+                });
+                if ui
+                    .button(format!("Aside ({})", data.state.aside_count))
+                    .clicked()
+                {
                     data.viewing_aside = true;
                 }
+                ui.separator();
+                random_tools(ui, data, to_server);
+                if let Some(notice) = &data.notice {
+                    ui.separator();
+                    ui.label(notice);
+                }
             });
         });
         sidebar(ctx, to_server, data);
         handbar(ctx, to_server, data);
         timeline(ctx, RelSide::Same, data, to_server);
+        distant_hand(ctx, data);
         timeline(ctx, RelSide::Other, data, to_server);
         middle(ctx, to_server, data);
 
@@ -138,6 +348,21 @@ pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameDa
                 });
         }
 
+        if data.viewing_history {
+            egui::Window::new("History")
+                .resizable(true)
+                .open(&mut data.viewing_history)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical()
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            for entry in &data.state.history {
+                                ui.label(describe(entry));
+                            }
+                        });
+                });
+        }
+
         if data.viewing_aside {
             egui::Window::new("Deck Editor")
                 .resizable(true)
@@ -178,16 +403,258 @@ pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameDa
                 });
         }
 
-        if !data.seaching.is_empty() {
-            egui::Window::new("Searching...")
+        if let Some(side) = data.viewing_discard {
+            let mut open = true;
+            let title = match side {
+                RelSide::Same => "Your discard",
+                RelSide::Other => "Their discard",
+            };
+            egui::Window::new(title)
+                .resizable(true)
+                .constrain(false)
+                .min_height(CARD_HEIGHT)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        let (_, dropped) = ui.dnd_drop_zone::<PlaceFrom, _>(Frame::new(), |ui| {
+                            ui.set_min_height(CARD_HEIGHT);
+                            ui.set_min_width(400.);
+                            egui::Grid::new("discardview").show(ui, |ui| {
+                                let discard = &data.state.get_player(side).discard;
+                                for (idx, card) in discard.iter().enumerate() {
+                                    let id = format!("discard_{:?}", card.id).into();
+                                    let zone = PlaceFrom::Discard(side, card.id);
+                                    drag(ui, id, zone, |ui| {
+                                        ui.add(
+                                            CardDisplay::new(card.clone(), to_server).at_zone(zone),
+                                        )
+                                    });
+                                    if idx % 8 == 7 {
+                                        ui.end_row();
+                                    }
+                                }
+                            });
+                        });
+
+                        if let Some(dropped) = dropped {
+                            to_server
+                                .send(ClientMsg::Move {
+                                    from: *dropped,
+                                    to: shared::PlaceTo::Discard(side),
+                                })
+                                .unwrap();
+                        }
+                    });
+                });
+            if !open {
+                data.viewing_discard = None;
+            }
+        }
+
+        if let Some(result) = data.state.result {
+            egui::Window::new("Game over")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.heading(match result.winner {
+                        Some(RelSide::Same) => "You won!",
+                        Some(RelSide::Other) => "You lost",
+                        None => "It's a draw",
+                    });
+                    ui.label(result.reason.get_name());
+                    let score = data.state.score;
+                    let next = if score.is_over() {
+                        ui.label(match score.winner {
+                            Some(RelSide::Same) => "You won the match!",
+                            Some(RelSide::Other) => "You lost the match",
+                            None => "The match is tied",
+                        });
+                        "New match"
+                    } else {
+                        "Next game"
+                    };
+                    if ui.button(next).clicked() {
+                        to_server.send(ClientMsg::Rematch).unwrap();
+                    }
+                });
+        } else if let Some(mulligan) = data.state.mulligan {
+            egui::Window::new("Opening hand")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_TOP, Vec2::new(0., 32.))
+                .show(ctx, |ui| {
+                    ui.label(match data.state.turn.whose {
+                        RelSide::Same => "You go first",
+                        RelSide::Other => "Your opponent goes first",
+                    });
+                    ui.label(format!("Mulligans: {}", mulligan.local.mulligans));
+                    if mulligan.local.kept {
+                        ui.label("Waiting for your opponent...");
+                        return;
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Mulligan").clicked() {
+                            to_server.send(ClientMsg::Mulligan).unwrap();
+                        }
+                        if ui.button("Keep").clicked() {
+                            to_server.send(ClientMsg::KeepHand).unwrap();
+                        }
+                    });
+                });
+        } else if data.state.draw_offer == Some(RelSide::Other) {
+            egui::Window::new("Draw offered")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("Your opponent offered a draw");
+                    if ui.button("Accept").clicked() {
+                        to_server.send(ClientMsg::AcceptDraw).unwrap();
+                    }
+                });
+        }
+
+        let fighting = data.state.turn.step == TurnStep::Combat || data.state.combat.is_some();
+        if fighting && data.state.result.is_none() && data.state.mulligan.is_none() {
+            combat_window(ctx, data, to_server);
+        }
+
+        if let Some((by, cards)) = &data.revealed {
+            let mut close = false;
+            egui::Window::new("Revealed")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(match by {
+                        RelSide::Same => "The home player revealed",
+                        RelSide::Other => "Your opponent revealed",
+                    });
+                    ui.horizontal_wrapped(|ui| {
+                        for card in cards {
+                            ui.add(CardDisplay::new(card.clone(), to_server));
+                        }
+                    });
+                    close = ui.button("Close").clicked();
+                });
+            if close {
+                data.revealed = None;
+            }
+        }
+
+        if let Some(looking) = &mut data.looking {
+            let mut done = false;
+            egui::Window::new("Top of deck")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} {}, top card first",
+                        match looking.side {
+                            RelSide::Same => "Your",
+                            RelSide::Other => "Their",
+                        },
+                        zone_name(Zone::Deck(looking.deck))
+                    ));
+                    let mut swap = None;
+                    egui::Grid::new("lookattop").show(ui, |ui| {
+                        let count = looking.cards.len();
+                        for (idx, (card, destination)) in looking.cards.iter_mut().enumerate() {
+                            if ui.add_enabled(idx > 0, egui::Button::new("^")).clicked() {
+                                swap = Some(idx - 1);
+                            }
+                            if ui
+                                .add_enabled(idx + 1 < count, egui::Button::new("v"))
+                                .clicked()
+                            {
+                                swap = Some(idx);
+                            }
+                            ui.label(&card.name);
+                            for option in [
+                                LookDestination::Top,
+                                LookDestination::Bottom,
+                                LookDestination::Hand,
+                                LookDestination::Discard,
+                            ] {
+                                ui.radio_value(destination, option, option.get_name());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    if let Some(idx) = swap {
+                        looking.cards.swap(idx, idx + 1);
+                    }
+                    if ui.button("Done").clicked() {
+                        to_server
+                            .send(ClientMsg::ArrangeTop(looking.arrangement()))
+                            .unwrap();
+                        done = true;
+                    }
+                });
+            if done {
+                data.looking = None;
+            }
+        }
+
+        if let Some(request) = data.state.search_request {
+            let what = match request.top {
+                Some(n) => format!("look at the top {n} of"),
+                None => "search".to_owned(),
+            };
+            egui::Window::new("Search request")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| match request.by {
+                    RelSide::Same => {
+                        ui.label(format!(
+                            "Waiting for your opponent to let you {what} their deck..."
+                        ));
+                    }
+                    RelSide::Other => {
+                        ui.label(format!(
+                            "Your opponent wants to {what} your {}",
+                            zone_name(Zone::Deck(request.deck))
+                        ));
+                        ui.horizontal(|ui| {
+                            if ui.button("Allow").clicked() {
+                                to_server.send(ClientMsg::AnswerSearch(true)).unwrap();
+                            }
+                            if ui.button("Deny").clicked() {
+                                to_server.send(ClientMsg::AnswerSearch(false)).unwrap();
+                            }
+                        });
+                    }
+                });
+        }
+
+        if let Some(searching) = &data.searching {
+            let mut finished = None;
+            let title = match searching.side {
+                RelSide::Same => {
+                    format!("Searching your {}", zone_name(Zone::Deck(searching.deck)))
+                }
+                RelSide::Other => {
+                    format!("Searching their {}", zone_name(Zone::Deck(searching.deck)))
+                }
+            };
+            egui::Window::new(title)
                 .resizable(true)
                 .scroll([false, true])
                 .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Filter by name");
+                        ui.text_edit_singleline(&mut data.search_filter);
+                    });
+                    let filter = data.search_filter.to_lowercase();
                     egui::ScrollArea::vertical().show(ui, |ui| {
                         egui::Grid::new("cardsearch").show(ui, |ui| {
-                            for (idx, card) in data.seaching.iter().enumerate() {
+                            let cards = searching
+                                .cards
+                                .iter()
+                                .filter(|x| x.name.to_lowercase().contains(&filter));
+                            for (idx, card) in cards.enumerate() {
                                 let id = format!("searching_{:?}", card.id).into();
-                                let zone = PlaceFrom::Deck(RelSide::Same, DeckType::Main, card.id);
+                                let zone = PlaceFrom::Deck(searching.side, searching.deck, card.id);
                                 drag(ui, id, zone, |ui| {
                                     ui.add(CardDisplay::new(card.clone(), to_server).at_zone(zone))
                                 });
@@ -197,20 +664,67 @@ pub async fn draw_game(to_server: &UnboundedSender<ClientMsg>, data: &mut GameDa
                             }
                         });
                     });
-                    if ui.button("Done").clicked() {
-                        data.seaching = vec![];
-                        to_server.send(ClientMsg::FinishSearch).unwrap();
-                    };
+                    ui.horizontal(|ui| {
+                        if ui.button("Done").clicked() {
+                            finished = Some(false);
+                        }
+                        if ui.button("Done and shuffle").clicked() {
+                            finished = Some(true);
+                        }
+                    });
                 });
+            if let Some(shuffle) = finished {
+                data.searching = None;
+                data.search_filter.clear();
+                to_server.send(ClientMsg::FinishSearch { shuffle }).unwrap();
+            }
         }
     });
 }
 
+/// The room's clock rules. Changing anything restarts both clocks.
+fn clock_settings(ui: &mut Ui, data: &GameData, to_server: &UnboundedSender<ClientMsg>) {
+    let mut enabled = data.state.clock_rules.is_some();
+    if ui.checkbox(&mut enabled, "Chess clock").changed() {
+        let rules = enabled.then(ClockRules::default);
+        to_server.send(ClientMsg::SetClock(rules)).unwrap();
+    }
+    let Some(current) = data.state.clock_rules else {
+        return;
+    };
+    let mut rules = current;
+    let mut minutes = rules.bank.as_secs() / 60;
+    let mut seconds = rules.increment.as_secs();
+    ui.add(egui::Slider::new(&mut minutes, 1..=60).text("Minutes"));
+    ui.add(egui::Slider::new(&mut seconds, 0..=60).text("Increment"));
+    rules.bank = Duration::from_secs(minutes * 60);
+    rules.increment = Duration::from_secs(seconds);
+    for rule in [TimeoutRule::Warn, TimeoutRule::AutoPass, TimeoutRule::Lose] {
+        ui.radio_value(&mut rules.timeout, rule, rule.get_name());
+    }
+    if rules != current {
+        to_server.send(ClientMsg::SetClock(Some(rules))).unwrap();
+    }
+}
+
+/// A player's time bank as minutes and seconds. The one that's running stands out.
+fn clock(ui: &mut Ui, left: Duration, running: bool) {
+    let secs = left.as_secs();
+    let text = egui::RichText::new(format!("{}:{:02}", secs / 60, secs % 60)).monospace();
+    let text = match (running, secs) {
+        (true, 0) => text.strong().color(Color32::RED),
+        (true, _) => text.strong().color(Color32::LIGHT_GREEN),
+        (false, _) => text.weak(),
+    };
+    ui.label(text);
+}
+
 pub fn turn_button(
     ui: &mut Ui,
     current_side: RelSide,
     current_step: TurnStep,
     step: TurnStep,
+    enabled: bool,
     to_server: &UnboundedSender<ClientMsg>,
 ) {
     let color = match current_step == step {
@@ -246,9 +760,14 @@ pub fn turn_button(
         TurnStep::Switch => TEXTURES.read().get_texture(ImageName::SwitchTurnBtn),
     };
 
+    let color = if enabled {
+        color
+    } else {
+        color.gamma_multiply(0.4)
+    };
     let btn = ImageButton::new(image).tint(color);
 
-    if ui.add(btn).clicked() {
+    if ui.add_enabled(enabled, btn).clicked() {
         to_server.send(ClientMsg::TurnSet(step)).unwrap();
     }
     ui.set_style(old_style);
@@ -257,6 +776,8 @@ pub fn turn_button(
 struct CardDisplay<'a> {
     card: LocalCard,
     location: Option<PlaceFrom>,
+    /// Marks hand cards the opponent knows about.
+    revealed: bool,
     sender: &'a UnboundedSender<ClientMsg>,
 }
 
@@ -265,6 +786,7 @@ impl<'a> CardDisplay<'a> {
         Self {
             card: name.into(),
             location: None,
+            revealed: false,
             sender,
         }
     }
@@ -276,6 +798,10 @@ impl<'a> CardDisplay<'a> {
             ..self
         }
     }
+
+    fn revealed(self, revealed: bool) -> Self {
+        Self { revealed, ..self }
+    }
 }
 
 impl Widget for CardDisplay<'_> {
@@ -283,6 +809,9 @@ impl Widget for CardDisplay<'_> {
         let (rect, response) =
             ui.allocate_exact_size(Vec2::new(CARD_WIDTH, CARD_HEIGHT), Sense::click());
 
+        let known = matches!(self.card.name, Hidden::Unhidden(_));
+        let known_face_down = self.card.face_down && known;
+
         let image = {
             let a = TEXTURES.read();
             if let Hidden::Unhidden(name) = self.card.name {
@@ -300,94 +829,203 @@ impl Widget for CardDisplay<'_> {
             let Some(location) = self.location else {
                 return;
             };
-            if matches!(location, PlaceFrom::Space(..)) {
-                ui.label("Stats");
-                ui.horizontal(|ui| {
-                    ui.add(CounterButton {
-                        counter: "HP".to_owned(),
-                        to_server: self.sender,
-                        current: self.card.counters.get("HP").cloned().unwrap_or_default(),
-                        from: location,
-                    })
-                });
-                ui.horizontal(|ui| {
-                    ui.add(CounterButton {
-                        counter: "DEF".to_owned(),
-                        to_server: self.sender,
-                        current: self.card.counters.get("DEF").cloned().unwrap_or_default(),
-                        from: location,
-                    })
-                });
-                ui.add(CounterButton {
-                    counter: "POW".to_owned(),
-                    to_server: self.sender,
-                    current: self.card.counters.get("POW").cloned().unwrap_or_default(),
-                    from: location,
-                });
+            if matches!(location, PlaceFrom::Space(..) | PlaceFrom::Timeline(..)) {
+                let flip = if self.card.face_down {
+                    "Turn face up"
+                } else {
+                    "Turn face down"
+                };
+                if ui.button(flip).clicked() {
+                    self.sender.send(ClientMsg::Flip(location)).unwrap();
+                }
+                if self.card.face_down {
+                    // The server also lets owners peek and reveal, but we don't know who owns what
+                    if location.side() == Some(RelSide::Same) {
+                        if ui.button("Peek").clicked() {
+                            self.sender.send(ClientMsg::Peek(location)).unwrap();
+                        }
+                        reveal_buttons(ui, vec![location], self.sender);
+                    }
+                }
+            }
+            if matches!(location, PlaceFrom::Hand(..)) {
+                reveal_buttons(ui, vec![location], self.sender);
             }
-            if !matches!(
-                location,
-                PlaceFrom::Hand(..)
-                    | PlaceFrom::Discard(..)
-                    | PlaceFrom::Deck(..)
-                    | PlaceFrom::Aside(..)
-            ) {
+            if matches!(location, PlaceFrom::Space(..) | PlaceFrom::Timeline(..)) {
+                for status in Status::ALL {
+                    let mut value = self.card.status.get(status);
+                    if ui.checkbox(&mut value, status.get_name()).changed() {
+                        self.sender
+                            .send(ClientMsg::SetStatus(location, status, value))
+                            .unwrap();
+                    }
+                }
                 ui.label("Counters");
-                ui.add(CounterButton {
-                    counter: "RED".to_owned(),
-                    to_server: self.sender,
-                    current: self.card.counters.get("RED").cloned().unwrap_or_default(),
-                    from: location,
-                });
-                ui.add(CounterButton {
-                    counter: "GRE".to_owned(),
-                    to_server: self.sender,
-                    current: self.card.counters.get("GRE").cloned().unwrap_or_default(),
-                    from: location,
-                });
-                ui.add(CounterButton {
-                    counter: "BLU".to_owned(),
-                    to_server: self.sender,
-                    current: self.card.counters.get("BLU").cloned().unwrap_or_default(),
-                    from: location,
-                });
-                ui.add(CounterButton {
-                    counter: "BLA".to_owned(),
-                    to_server: self.sender,
-                    current: self.card.counters.get("BLA").cloned().unwrap_or_default(),
-                    from: location,
+                // Creatures always offer their combat stats
+                let stats = matches!(location, PlaceFrom::Space(..));
+                counter_list(
+                    ui,
+                    CounterTarget::Card(location),
+                    &self.card.counters,
+                    stats,
+                    self.sender,
+                );
+            }
+            if known {
+                ui.menu_button("Copy", |ui| {
+                    board_places(ui, |to| {
+                        self.sender
+                            .send(ClientMsg::CopyCard { from: location, to })
+                            .unwrap();
+                    })
                 });
             }
+            if !matches!(location, PlaceFrom::Deck(..)) && ui.button("Return to owner").clicked() {
+                self.sender
+                    .send(ClientMsg::ReturnToOwner(location))
+                    .unwrap();
+            }
         });
 
-        egui::Image::new(image.clone()).paint_at(ui, rect);
+        let status = self.card.status;
+        if status.exhausted {
+            // Turned sideways, and shrunk so it still fits where the card was
+            let size = Vec2::new(CARD_WIDTH * CARD_WIDTH / CARD_HEIGHT, CARD_WIDTH);
+            egui::Image::new(image.clone())
+                .rotate(std::f32::consts::FRAC_PI_2, Vec2::splat(0.5))
+                .paint_at(ui, egui::Rect::from_center_size(rect.center(), size));
+        } else {
+            egui::Image::new(image.clone()).paint_at(ui, rect);
+        }
+        if status.summoning_sick {
+            ui.painter()
+                .rect_filled(rect, 0.0, Color32::from_rgba_unmultiplied(90, 160, 90, 70));
+            ui.painter().text(
+                rect.center() + Vec2::new(0., 16.),
+                Align2::CENTER_CENTER,
+                "Summoning sick",
+                FontId::proportional(11.),
+                Color32::WHITE,
+            );
+        }
+        if status.marked {
+            ui.painter().rect_stroke(
+                rect,
+                0.0,
+                egui::Stroke::new(3., Color32::GOLD),
+                egui::StrokeKind::Inside,
+            );
+        }
+        counter_badges(ui, rect, &self.card.counters);
+        if self.card.token {
+            ui.painter().text(
+                rect.right_top() + Vec2::new(-2., 2.),
+                Align2::RIGHT_TOP,
+                "Token",
+                FontId::proportional(11.),
+                Color32::LIGHT_BLUE,
+            );
+        }
+        if known_face_down {
+            ui.painter()
+                .rect_filled(rect, 0.0, Color32::from_black_alpha(140));
+            ui.painter().text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                "Face down",
+                FontId::proportional(12.),
+                Color32::WHITE,
+            );
+        }
+        if self.revealed {
+            ui.painter().text(
+                rect.center_bottom(),
+                Align2::CENTER_BOTTOM,
+                "Revealed",
+                FontId::proportional(12.),
+                Color32::YELLOW,
+            );
+        }
         response
     }
 }
 
+fn reveal_buttons(ui: &mut Ui, cards: Vec<PlaceFrom>, to_server: &UnboundedSender<ClientMsg>) {
+    if ui.button("Reveal").clicked() {
+        to_server
+            .send(ClientMsg::Reveal {
+                cards: cards.clone(),
+                to: RevealTarget::Opponent,
+            })
+            .unwrap();
+    }
+    if ui.button("Reveal to everyone").clicked() {
+        to_server
+            .send(ClientMsg::Reveal {
+                cards,
+                to: RevealTarget::Everyone,
+            })
+            .unwrap();
+    }
+}
+
 struct CounterButton<'a> {
     counter: String,
     to_server: &'a UnboundedSender<ClientMsg>,
-    current: usize,
-    from: PlaceFrom,
+    current: isize,
+    target: CounterTarget,
 }
 
 impl Widget for CounterButton<'_> {
     fn ui(self, ui: &mut egui::Ui) -> Response {
+        // Holding shift changes counters five at a time
+        let step = if is_key_down(KeyCode::LeftShift) {
+            5
+        } else {
+            1
+        };
         ui.horizontal(|ui| {
             let add = ui.button("+");
             let rem = ui.button("-");
-            ui.label(format!("{}: {}", self.counter.to_uppercase(), self.current));
+            ui.label(
+                egui::RichText::new(self.counter.to_uppercase())
+                    .strong()
+                    .color(counter_color(&self.counter)),
+            );
+            let mut value = self.current;
+            let set = ui.add(egui::DragValue::new(&mut value));
+            let remove = ui.small_button("x");
 
             if add.clicked() {
                 self.to_server
-                    .send(ClientMsg::AddCounter(self.from, self.counter.clone(), true))
+                    .send(ClientMsg::AddCounter(
+                        self.target,
+                        self.counter.clone(),
+                        step,
+                    ))
                     .unwrap();
             }
-
             if rem.clicked() {
                 self.to_server
-                    .send(ClientMsg::AddCounter(self.from, self.counter, false))
+                    .send(ClientMsg::AddCounter(
+                        self.target,
+                        self.counter.clone(),
+                        -step,
+                    ))
+                    .unwrap();
+            }
+            if set.changed() {
+                self.to_server
+                    .send(ClientMsg::SetCounter(
+                        vec![self.target],
+                        self.counter.clone(),
+                        value,
+                    ))
+                    .unwrap();
+            }
+            if remove.clicked() {
+                self.to_server
+                    .send(ClientMsg::RemoveCounter(self.target, self.counter))
                     .unwrap();
             }
         })
@@ -395,6 +1033,168 @@ impl Widget for CounterButton<'_> {
     }
 }
 
+/// Combat stats go first, then everything else by name.
+fn counter_order(name: &str) -> (usize, &str) {
+    let stat = [HEALTH, DEFENSE, POWER]
+        .iter()
+        .position(|x| *x == name)
+        .unwrap_or(3);
+    (stat, name)
+}
+
+fn sorted_counters(counters: &HashMap<String, isize>) -> Vec<(&str, isize)> {
+    let mut sorted: Vec<_> = counters.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    sorted.sort_by_key(|(name, _)| counter_order(name));
+    sorted
+}
+
+/// The old coloured counters keep their colours. Anything else gets one picked from its name,
+/// so the same counter always looks the same.
+fn counter_color(name: &str) -> Color32 {
+    match name.to_uppercase().as_str() {
+        HEALTH => Color32::from_rgb(220, 70, 70),
+        DEFENSE => Color32::from_rgb(110, 150, 220),
+        POWER => Color32::from_rgb(235, 170, 60),
+        "RED" => Color32::RED,
+        "GRE" => Color32::GREEN,
+        "BLU" => Color32::from_rgb(60, 90, 255),
+        "BLA" => Color32::from_gray(30),
+        name => {
+            let hash = name
+                .bytes()
+                .fold(0u32, |acc, x| acc.wrapping_mul(31).wrapping_add(x.into()));
+            egui::ecolor::Hsva::new((hash % 360) as f32 / 360., 0.6, 0.85, 1.).into()
+        }
+    }
+}
+
+/// Dark text on light counters and the other way around.
+fn text_color(background: Color32) -> Color32 {
+    let [r, g, b, _] = background.to_array();
+    let luma = 0.3 * f32::from(r) + 0.59 * f32::from(g) + 0.11 * f32::from(b);
+    if luma > 128. {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+/// Every counter on a card, stacked down its left edge.
+fn counter_badges(ui: &Ui, rect: egui::Rect, counters: &HashMap<String, isize>) {
+    let mut pos = rect.left_top() + Vec2::new(2., 2.);
+    for (name, value) in sorted_counters(counters) {
+        let color = counter_color(name);
+        let galley = ui.painter().layout_no_wrap(
+            format!("{} {value}", name.to_uppercase()),
+            FontId::proportional(11.),
+            text_color(color),
+        );
+        let badge = egui::Rect::from_min_size(pos, galley.size() + Vec2::new(6., 2.));
+        ui.painter().rect_filled(badge, 3., color);
+        ui.painter()
+            .galley(pos + Vec2::new(3., 1.), galley, Color32::WHITE);
+        pos.y += badge.height() + 2.;
+    }
+}
+
+/// A button for every counter on something, and a way to add new ones.
+fn counter_list(
+    ui: &mut Ui,
+    target: CounterTarget,
+    counters: &HashMap<String, isize>,
+    stats: bool,
+    to_server: &UnboundedSender<ClientMsg>,
+) {
+    let mut shown = sorted_counters(counters);
+    if stats {
+        for stat in [HEALTH, DEFENSE, POWER] {
+            if !counters.contains_key(stat) {
+                shown.push((stat, 0));
+            }
+        }
+        shown.sort_by_key(|(name, _)| counter_order(name));
+    }
+    for (name, value) in shown {
+        ui.add(CounterButton {
+            counter: name.to_owned(),
+            to_server,
+            current: value,
+            target,
+        });
+    }
+    let id = Id::new(("new_counter", format!("{target:?}")));
+    let mut name = ui.data_mut(|x| x.get_temp::<String>(id).unwrap_or_default());
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut name).desired_width(64.));
+        let counter = name.trim().to_uppercase();
+        if ui
+            .add_enabled(!counter.is_empty(), egui::Button::new("New counter"))
+            .clicked()
+        {
+            to_server
+                .send(ClientMsg::CreateCounter(target, counter))
+                .unwrap();
+            name.clear();
+        }
+    });
+    ui.data_mut(|x| x.insert_temp(id, name));
+}
+
+/// The counters on a space's card, in their colours.
+fn stat_line(ui: &mut Ui, card: Option<&LocalCard>) {
+    let Some(card) = card else {
+        ui.label("Empty");
+        return;
+    };
+    if card.counters.is_empty() {
+        ui.label("No counters");
+        return;
+    }
+    ui.horizontal_wrapped(|ui| {
+        for (name, value) in sorted_counters(&card.counters) {
+            ui.label(
+                egui::RichText::new(format!("{} {value}", name.to_uppercase()))
+                    .color(counter_color(name)),
+            );
+        }
+    });
+}
+
+/// Sets one counter on a whole row of creatures, or both.
+fn bulk_counters(ui: &mut Ui, data: &mut GameData, to_server: &UnboundedSender<ClientMsg>) {
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut data.bulk_counter).desired_width(64.));
+        ui.add(egui::DragValue::new(&mut data.bulk_value));
+    });
+    let counter = data.bulk_counter.trim().to_uppercase();
+    for (label, sides) in [
+        ("Set on your creatures", vec![RelSide::Same]),
+        ("Set on their creatures", vec![RelSide::Other]),
+        ("Set on every creature", vec![RelSide::Same, RelSide::Other]),
+    ] {
+        let targets: Vec<CounterTarget> = sides
+            .into_iter()
+            .flat_map(|side| {
+                let row = data.state.get_row(side);
+                row.spaces()
+                    .filter(|x| row[*x].is_some())
+                    .map(move |x| CounterTarget::Card(PlaceFrom::Space(side, x)))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let enabled = !counter.is_empty() && !targets.is_empty();
+        if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+            to_server
+                .send(ClientMsg::SetCounter(
+                    targets,
+                    counter.clone(),
+                    data.bulk_value,
+                ))
+                .unwrap();
+        }
+    }
+}
+
 fn timeline(
     ctx: &Context,
     side: RelSide,
@@ -455,43 +1255,42 @@ fn board_row(
     to_server: &UnboundedSender<ClientMsg>,
     ui: &mut egui::Ui,
 ) {
-    let spaces = match side {
-        RelSide::Same => [Space::First, Space::Second, Space::Third, Space::Fourth],
-        RelSide::Other => [Space::Fourth, Space::Third, Space::Second, Space::First],
+    let row = data.state.get_row(side);
+    let spaces: Vec<Space> = match side {
+        RelSide::Same => row.spaces().collect(),
+        RelSide::Other => row.spaces().rev().collect(),
     };
     for space in spaces {
         let frame = Frame::new();
-        let mut text = String::from("Empty");
-        if let Some(card) = &data.state.get_row(side)[space] {
-            text = format!(
-                "{} / {} / {}",
-                card.counters
-                    .get("HP")
-                    .copied()
-                    .map(|x| x.to_string())
-                    .unwrap_or("X".to_string()),
-                card.counters
-                    .get("DEF")
-                    .copied()
-                    .map(|x| x.to_string())
-                    .unwrap_or("X".to_string()),
-                card.counters
-                    .get("POW")
-                    .copied()
-                    .map(|x| x.to_string())
-                    .unwrap_or("X".to_string()),
-            );
-        }
         ui.vertical_centered(|ui| {
             if side == RelSide::Other {
-                ui.label(text.clone());
+                stat_line(ui, data.state.get_row(side)[space].as_ref());
             }
             let (_, dropped_item) = ui.dnd_drop_zone::<PlaceFrom, _>(frame, |ui| {
                 if let Some(card) = &data.state.get_row(side)[space] {
+                    // Attachments peek out above their host, the first one highest up
+                    let card_size = Vec2::new(CARD_WIDTH, CARD_HEIGHT);
+                    let fan = ATTACHMENT_OFFSET * card.attached.len() as f32;
+                    let (stack, _) =
+                        ui.allocate_exact_size(card_size + Vec2::new(0., fan), Sense::hover());
+                    for (idx, attached) in card.attached.iter().enumerate() {
+                        let offset = Vec2::new(0., ATTACHMENT_OFFSET * idx as f32);
+                        let rect = egui::Rect::from_min_size(stack.min + offset, card_size);
+                        let id = format!("attached_{side:?}_{space:?}_{idx}").into();
+                        let zone = PlaceFrom::Attached(side, space, attached.id);
+                        ui.scope_builder(UiBuilder::new().max_rect(rect), |ui| {
+                            drag(ui, id, zone, |ui| {
+                                ui.add(CardDisplay::new(attached.clone(), to_server).at_zone(zone))
+                            })
+                        });
+                    }
+                    let rect = egui::Rect::from_min_size(stack.min + Vec2::new(0., fan), card_size);
                     let id = format!("space_{side:?}_{space:?}").into();
                     let zone = PlaceFrom::Space(side, space);
-                    drag(ui, id, PlaceFrom::Space(side, space), |ui| {
-                        ui.add(CardDisplay::new(card.clone(), to_server).at_zone(zone))
+                    ui.scope_builder(UiBuilder::new().max_rect(rect), |ui| {
+                        drag(ui, id, zone, |ui| {
+                            ui.add(CardDisplay::new(card.clone(), to_server).at_zone(zone))
+                        })
                     });
                 } else {
                     Frame::new().show(ui, |ui| {
@@ -505,21 +1304,31 @@ fn board_row(
             });
 
             if let Some(dropped_item) = dropped_item {
+                // Holding control attaches the card to whatever is already there
+                let attaching =
+                    is_key_down(KeyCode::LeftControl) && data.state.get_row(side)[space].is_some();
+                let to = if attaching {
+                    PlaceTo::Attached(side, space)
+                } else {
+                    PlaceTo::Space(side, space, is_key_down(KeyCode::LeftShift))
+                };
                 to_server
                     .send(ClientMsg::Move {
                         from: *dropped_item,
-                        to: shared::PlaceTo::Space(side, space, is_key_down(KeyCode::LeftShift)),
+                        to: to.clone(),
                     })
                     .unwrap();
-                if let Some(card) = data.state.pop_card(*dropped_item) {
-                    data.state.push_card(
-                        card,
-                        shared::PlaceTo::Space(side, space, is_key_down(KeyCode::LeftShift)),
-                    );
+                // Attaching waits for the server, since it might not find anything to attach to
+                let moved = match attaching {
+                    true => None,
+                    false => data.state.pop_card(*dropped_item),
+                };
+                if let Some(card) = moved {
+                    data.state.push_card(card, to);
                 }
             }
             if side == RelSide::Same {
-                ui.label(text);
+                stat_line(ui, data.state.get_row(side)[space].as_ref());
             }
         });
     }
@@ -530,7 +1339,7 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
         .default_width(SIDEBAR_WIDTH)
         .show(ctx, |ui| {
             let (_, dropped_load) = ui.dnd_drop_zone::<PlaceFrom, _>(Frame::new(), |ui| {
-                if let Some(card) = data.state.distant_state.discard.first() {
+                if let Some(card) = data.state.distant_state.discard.last() {
                     let zone = PlaceFrom::Discard(RelSide::Other, card.id);
                     drag(ui, "discard_away".into(), zone, |ui| {
                         ui.add(CardDisplay::new(card.clone(), to_server).at_zone(zone))
@@ -549,6 +1358,10 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                     });
                 }
             });
+            let discard = data.state.distant_state.discard.len();
+            if ui.button(format!("Their discard ({discard})")).clicked() {
+                data.viewing_discard = Some(RelSide::Other);
+            }
             ui.horizontal(|ui| {
                 if ui.button("+").clicked() {
                     to_server
@@ -562,6 +1375,29 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                 }
                 ui.label(format!("Blood: {}", data.state.distant_state.blood));
             });
+            health_line(
+                ui,
+                RelSide::Other,
+                data.state.distant_state.health,
+                to_server,
+            );
+            counter_list(
+                ui,
+                CounterTarget::Player(RelSide::Other),
+                &data.state.distant_state.counters,
+                false,
+                to_server,
+            );
+            for deck in [DeckType::Main, DeckType::Blood] {
+                if let LocalDeckTop::Revealed(name) = data.state.distant_state.get_deck_top(deck) {
+                    ui.label(format!("Their {} top: {name}", zone_name(Zone::Deck(deck))));
+                }
+            }
+            let distant = &data.state.distant_state;
+            ui.label(format!(
+                "Their decks: {} main, {} blood",
+                distant.main_deck_size, distant.blood_deck_size
+            ));
 
             if let Some(load) = dropped_load {
                 to_server
@@ -588,12 +1424,13 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                         if ui.button("Shuffle").clicked() {
                             to_server.send(ClientMsg::Shuffle(DeckType::Main)).unwrap();
                         }
-                        if ui.button("Search").clicked() {
-                            to_server
-                                .send(ClientMsg::RequestSearch(DeckType::Main))
-                                .unwrap();
-                        }
+                        deck_menu(ui, DeckType::Main, data, to_server);
                     });
+                    size_badge(
+                        ui,
+                        &main_draw,
+                        data.state.local_state.deck_size(DeckType::Main),
+                    );
                     if main_draw.clicked() {
                         to_server
                             .send(ClientMsg::Draw(RelSide::Same, DeckType::Main))
@@ -630,6 +1467,11 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                             .get_texture(data.state.local_state.blood_deck_top.clone().into())
                             .clone(),
                     ));
+                    size_badge(
+                        ui,
+                        &blood_draw,
+                        data.state.local_state.deck_size(DeckType::Blood),
+                    );
                     if blood_draw.clicked() {
                         to_server
                             .send(ClientMsg::Draw(RelSide::Same, DeckType::Blood))
@@ -639,6 +1481,7 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                         if ui.button("Shuffle").clicked() {
                             to_server.send(ClientMsg::Shuffle(DeckType::Blood)).unwrap();
                         }
+                        deck_menu(ui, DeckType::Blood, data, to_server);
                     });
                 });
                 if let Some(dropped) = dropped {
@@ -665,7 +1508,7 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                 }
                 let frame = Frame::new();
                 let (_, dropped_load) = ui.dnd_drop_zone::<PlaceFrom, _>(frame, |ui| {
-                    if let Some(card) = data.state.local_state.discard.first() {
+                    if let Some(card) = data.state.local_state.discard.last() {
                         let zone = PlaceFrom::Discard(RelSide::Same, card.id);
                         drag(ui, "discard".into(), zone, |ui| {
                             ui.add(CardDisplay::new(card.clone(), to_server).at_zone(zone))
@@ -693,6 +1536,10 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                             .push_card(card, shared::PlaceTo::Discard(RelSide::Same));
                     }
                 }
+                let discard = data.state.local_state.discard.len();
+                if ui.button(format!("Your discard ({discard})")).clicked() {
+                    data.viewing_discard = Some(RelSide::Same);
+                }
 
                 ui.horizontal(|ui| {
                     if ui.button("+").clicked() {
@@ -708,40 +1555,99 @@ fn sidebar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                     ui.label(format!("Blood: {}", data.state.local_state.blood));
                 });
 
-                ui.with_layout(
-                    Layout::bottom_up(Align::Center).with_main_justify(true),
-                    |ui| {
-                        ui.horizontal(|ui| {
-                            if ui.button("+").clicked() {
-                                to_server.send(ClientMsg::AddHealth(true)).unwrap();
-                            }
-                            if ui.button("-").clicked() {
-                                to_server.send(ClientMsg::AddHealth(false)).unwrap();
-                            }
-                            ui.label(format!("Health: {}", data.state.health));
-                        });
-                    },
+                health_line(ui, RelSide::Same, data.state.local_state.health, to_server);
+                counter_list(
+                    ui,
+                    CounterTarget::Player(RelSide::Same),
+                    &data.state.local_state.counters,
+                    false,
+                    to_server,
                 );
             });
         });
 }
 
+/// The number of cards in a pile, in the corner of it.
+fn size_badge(ui: &mut Ui, pile: &Response, size: usize) {
+    let center = pile.rect.right_top() + Vec2::new(-12., 12.);
+    ui.painter()
+        .circle_filled(center, 10., Color32::from_black_alpha(200));
+    ui.painter().text(
+        center,
+        Align2::CENTER_CENTER,
+        size.to_string(),
+        FontId::proportional(12.),
+        Color32::WHITE,
+    );
+}
+
+/// The backs of the opponent's hand, with anything they revealed face up.
+fn distant_hand(ctx: &Context, data: &GameData) {
+    let distant = &data.state.distant_state;
+    let size = Vec2::new(CARD_WIDTH, CARD_HEIGHT) / 2.;
+    egui::TopBottomPanel::top("other_hand").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            ui.set_min_height(size.y);
+            ui.label(format!("Hand: {}", distant.hand_count));
+            let hidden = distant
+                .hand_count
+                .saturating_sub(distant.revealed_hand.len());
+            let textures = TEXTURES.read();
+            for card in &distant.revealed_hand {
+                let image = textures.get_texture(ImageName::Name(card.name.clone()));
+                ui.add(egui::Image::new(image.clone()).fit_to_exact_size(size))
+                    .on_hover_ui_at_pointer(|ui| {
+                        ui.image(image.clone());
+                    });
+            }
+            let back = textures.get_texture(ImageName::CardBack);
+            for _ in 0..hidden {
+                ui.add(egui::Image::new(back.clone()).fit_to_exact_size(size));
+            }
+        });
+    });
+}
+
+fn health_line(ui: &mut Ui, side: RelSide, health: usize, to_server: &UnboundedSender<ClientMsg>) {
+    // Holding shift changes health five at a time
+    let step = if is_key_down(KeyCode::LeftShift) {
+        5
+    } else {
+        1
+    };
+    ui.horizontal(|ui| {
+        if ui.button("+").clicked() {
+            to_server.send(ClientMsg::AddHealth(side, step)).unwrap();
+        }
+        if ui.button("-").clicked() {
+            to_server.send(ClientMsg::AddHealth(side, -step)).unwrap();
+        }
+        ui.label(format!("Health: {health}"));
+    });
+}
+
 fn handbar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut GameData) {
     egui::TopBottomPanel::bottom("hand")
         .min_height(HANDBAR_HEIGHT)
         .default_height(HANDBAR_HEIGHT)
         .show(ctx, |ui| {
             let frame = Frame::new();
-            let (_, moved) = ui.dnd_drop_zone::<PlaceFrom, _>(frame, |ui| {
+            let (hand, moved) = ui.dnd_drop_zone::<PlaceFrom, _>(frame, |ui| {
                 ui.horizontal_wrapped(|ui| {
                     ui.set_min_height(HANDBAR_HEIGHT);
+                    let revealed = &data.state.local_state.revealed_hand;
                     for (idx, card) in data.state.hand.iter().enumerate() {
                         let id = format!("hand_{idx}").into();
                         let zone = PlaceFrom::Hand(card.id);
+                        let known = revealed.iter().any(|x| x.id == card.id);
                         drag(ui, id, PlaceFrom::Hand(card.id), |ui| {
                             Frame::new()
                                 .show(ui, |ui| {
-                                    ui.add(CardDisplay::new(card.clone(), to_server).at_zone(zone))
+                                    ui.add(
+                                        CardDisplay::new(card.clone(), to_server)
+                                            .at_zone(zone)
+                                            .revealed(known),
+                                    )
                                 })
                                 .inner
                         });
@@ -752,6 +1658,16 @@ fn handbar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
                     ui.add_space(ui.available_width());
                 });
             });
+            hand.response.context_menu(|ui| {
+                let cards = data
+                    .state
+                    .hand
+                    .iter()
+                    .map(|x| PlaceFrom::Hand(x.id))
+                    .collect();
+                ui.label("Whole hand");
+                reveal_buttons(ui, cards, to_server);
+            });
 
             if let Some(moved) = moved {
                 to_server
@@ -769,11 +1685,14 @@ fn handbar(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Gam
 
 fn middle(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut GameData) {
     egui::CentralPanel::default().show(ctx, |ui| {
+        let table = ui.max_rect();
+        let background = ui.interact(table, Id::new("table"), Sense::hover());
         let height = ui.available_height() / 2.;
         ui.add_space(height - CARD_HEIGHT);
         ui.horizontal(|ui| {
             let width = ui.available_width() / 2.;
-            ui.add_space(width - (CARD_WIDTH + 2.) * 2.);
+            let spaces = data.state.local_row.len() as f32;
+            ui.add_space(width - (CARD_WIDTH + 2.) * spaces / 2.);
             egui::Grid::new("spaces")
                 .spacing(Vec2::new(2., 2.))
                 .show(ui, |ui| {
@@ -782,9 +1701,45 @@ fn middle(ctx: &Context, to_server: &UnboundedSender<ClientMsg>, data: &mut Game
                     board_row(RelSide::Same, data, to_server, ui);
                 });
         });
+        floating_cards(ui, table.min, data, to_server);
+
+        // Anything not dropped in a space lands on the table, centered where it was let go
+        let dropped = background.dnd_release_payload::<PlaceFrom>();
+        if let (Some(from), Some(pointer)) = (dropped, ctx.pointer_latest_pos()) {
+            let corner = pointer - table.min - Vec2::new(CARD_WIDTH, CARD_HEIGHT) / 2.;
+            let to = PlaceTo::Floating(corner.x.max(0.) as usize, corner.y.max(0.) as usize);
+            to_server
+                .send(ClientMsg::Move {
+                    from: *from,
+                    to: to.clone(),
+                })
+                .unwrap();
+            if let Some(card) = data.state.pop_card(*from) {
+                data.state.push_card(card, to);
+            }
+        }
     });
 }
 
+/// The cards lying loose on the table. Either player can pick them up.
+fn floating_cards(
+    ui: &mut Ui,
+    origin: egui::Pos2,
+    data: &GameData,
+    to_server: &UnboundedSender<ClientMsg>,
+) {
+    for (card, (x, y)) in &data.state.floating_cards {
+        let min = origin + Vec2::new(*x as f32, *y as f32);
+        let rect = egui::Rect::from_min_size(min, Vec2::new(CARD_WIDTH, CARD_HEIGHT));
+        let id = format!("floating_{:?}", card.id).into();
+        let zone = PlaceFrom::Floating(card.id);
+        let mut ui = ui.new_child(UiBuilder::new().max_rect(rect));
+        drag(&mut ui, id, zone, |ui| {
+            ui.add(CardDisplay::new(card.clone(), to_server).at_zone(zone))
+        });
+    }
+}
+
 fn drag(
     ui: &mut egui::Ui,
     id: Id,
@@ -823,3 +1778,466 @@ fn drag(
         InnerResponse::new(inner, response)
     }
 }
+
+fn random_tools(ui: &mut Ui, data: &mut GameData, to_server: &UnboundedSender<ClientMsg>) {
+    if ui.button("Flip coin").clicked() {
+        to_server.send(ClientMsg::FlipCoin).unwrap();
+    }
+    ui.add(egui::DragValue::new(&mut data.dice_count).range(1..=MAX_DICE));
+    ui.label("d");
+    ui.add(egui::DragValue::new(&mut data.dice_sides).range(1..=1000));
+    if ui.button("Roll").clicked() {
+        to_server
+            .send(ClientMsg::Roll {
+                sides: data.dice_sides,
+                count: data.dice_count,
+            })
+            .unwrap();
+    }
+    ui.menu_button("Random card", |ui| {
+        for side in [RelSide::Same, RelSide::Other] {
+            let whose = match side {
+                RelSide::Same => "Your",
+                RelSide::Other => "Their",
+            };
+            for zone in [
+                Zone::Hand,
+                Zone::Deck(DeckType::Main),
+                Zone::Deck(DeckType::Blood),
+                Zone::Discard,
+                Zone::Timeline,
+                Zone::Row,
+            ] {
+                if ui.button(format!("{whose} {}", zone_name(zone))).clicked() {
+                    to_server.send(ClientMsg::PickRandom(side, zone)).unwrap();
+                }
+            }
+        }
+        if ui.button("Aside").clicked() {
+            to_server
+                .send(ClientMsg::PickRandom(RelSide::Same, Zone::Aside))
+                .unwrap();
+        }
+    });
+    if ui.button("History").clicked() {
+        data.viewing_history = true;
+    }
+    if let Some(entry) = data.state.history.last() {
+        ui.label(describe(entry));
+    }
+}
+
+/// How much of each attached card shows above its host.
+const ATTACHMENT_OFFSET: f32 = 18.;
+
+/// Where [`draw_game`] leaves the row length for widgets that can't see the game state.
+const ROW_LEN: &str = "row_len";
+
+/// A button for every place a token can go. Clicking one calls `chosen` with it.
+fn board_places(ui: &mut Ui, chosen: impl Fn(PlaceTo)) {
+    let spaces = ui.data(|x| x.get_temp(Id::new(ROW_LEN)).unwrap_or(DEFAULT_SPACES));
+    if ui.button("The table").clicked() {
+        chosen(PlaceTo::Floating(0, 0));
+    }
+    for side in [RelSide::Same, RelSide::Other] {
+        let whose = match side {
+            RelSide::Same => "Your",
+            RelSide::Other => "Their",
+        };
+        if ui.button(format!("{whose} timeline")).clicked() {
+            chosen(PlaceTo::Timeline(side));
+        }
+        for space in (0..spaces).map(Space) {
+            if ui
+                .button(format!("{whose} space {}", space.0 + 1))
+                .clicked()
+            {
+                chosen(PlaceTo::Space(side, space, false));
+            }
+        }
+    }
+}
+
+fn space_name(side: RelSide, space: Space) -> String {
+    let whose = match side {
+        RelSide::Same => "your",
+        RelSide::Other => "their",
+    };
+    format!("{whose} space {}", space.0 + 1)
+}
+
+fn combat_window(ctx: &Context, data: &mut GameData, to_server: &UnboundedSender<ClientMsg>) {
+    egui::Window::new("Combat")
+        .collapsible(true)
+        .resizable(false)
+        .anchor(Align2::RIGHT_TOP, Vec2::new(-8., 32.))
+        .show(ctx, |ui| {
+            let Some(combat) = data.state.combat.clone() else {
+                if data.state.turn.whose == RelSide::Other {
+                    ui.label("Waiting for your opponent to attack...");
+                    return;
+                }
+                let targets: Vec<AttackTarget> = std::iter::once(AttackTarget::Player)
+                    .chain(
+                        data.state
+                            .distant_row
+                            .spaces()
+                            .filter(|x| data.state.distant_row[*x].is_some())
+                            .map(AttackTarget::Space),
+                    )
+                    .collect();
+                let target_name = |target: Option<AttackTarget>| match target {
+                    None => "Don't attack".to_owned(),
+                    Some(AttackTarget::Player) => "Your opponent".to_owned(),
+                    Some(AttackTarget::Space(space)) => space_name(RelSide::Other, space),
+                };
+                let spaces: Vec<Space> = data.state.local_row.spaces().collect();
+                data.attack_plan.resize(spaces.len(), None);
+                for &space in &spaces {
+                    if data.state.local_row[space].is_none() {
+                        continue;
+                    }
+                    let plan = &mut data.attack_plan[space.0];
+                    egui::ComboBox::from_label(space_name(RelSide::Same, space))
+                        .selected_text(target_name(*plan))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(plan, None, target_name(None));
+                            for target in &targets {
+                                ui.selectable_value(
+                                    plan,
+                                    Some(*target),
+                                    target_name(Some(*target)),
+                                );
+                            }
+                        });
+                }
+                let attacks: Vec<Attack> = spaces
+                    .into_iter()
+                    .filter(|x| data.state.local_row[*x].is_some())
+                    .filter_map(|from| {
+                        data.attack_plan[from.0].map(|target| Attack { from, target })
+                    })
+                    .collect();
+                if ui
+                    .add_enabled(!attacks.is_empty(), egui::Button::new("Declare attack"))
+                    .clicked()
+                {
+                    to_server.send(ClientMsg::DeclareAttack(attacks)).unwrap();
+                    data.attack_plan.clear();
+                }
+                return;
+            };
+
+            let attacker = combat.attacker;
+            let defender = attacker.opposite();
+            for attack in &combat.attacks {
+                let target = match attack.target {
+                    AttackTarget::Player if defender == RelSide::Same => "you".to_owned(),
+                    AttackTarget::Player => "your opponent".to_owned(),
+                    AttackTarget::Space(space) => space_name(defender, space),
+                };
+                let blocked = combat
+                    .blocks
+                    .iter()
+                    .find(|x| x.attacker == attack.from)
+                    .map(|x| format!(", blocked by {}", space_name(defender, x.blocker)))
+                    .unwrap_or_default();
+                ui.label(format!(
+                    "{} attacks {target}{blocked}",
+                    space_name(attacker, attack.from)
+                ));
+            }
+
+            if defender == RelSide::Same {
+                ui.separator();
+                let attacker_name = |space: Option<Space>| match space {
+                    None => "Don't block".to_owned(),
+                    Some(space) => format!("Block {}", space_name(RelSide::Other, space)),
+                };
+                let spaces: Vec<Space> = data.state.local_row.spaces().collect();
+                data.block_plan.resize(spaces.len(), None);
+                for &space in &spaces {
+                    if data.state.local_row[space].is_none() {
+                        continue;
+                    }
+                    let plan = &mut data.block_plan[space.0];
+                    egui::ComboBox::from_label(space_name(RelSide::Same, space))
+                        .selected_text(attacker_name(*plan))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(plan, None, attacker_name(None));
+                            for attack in &combat.attacks {
+                                let from = Some(attack.from);
+                                ui.selectable_value(plan, from, attacker_name(from));
+                            }
+                        });
+                }
+                if ui.button("Declare blocks").clicked() {
+                    let blocks = spaces
+                        .into_iter()
+                        .filter(|x| data.state.local_row[*x].is_some())
+                        .filter_map(|blocker| {
+                            data.block_plan[blocker.0].map(|attacker| Block { blocker, attacker })
+                        })
+                        .collect();
+                    to_server.send(ClientMsg::DeclareBlocks(blocks)).unwrap();
+                }
+            }
+
+            ui.separator();
+            if combat.hits.is_empty() {
+                ui.label("Nobody gets hurt");
+            }
+            for hit in &combat.hits {
+                ui.label(hit_name(hit));
+            }
+            ui.horizontal(|ui| {
+                if combat.local_confirmed {
+                    ui.label("Waiting for your opponent to confirm...");
+                } else if ui.button("Confirm").clicked() {
+                    to_server.send(ClientMsg::ConfirmCombat).unwrap();
+                }
+                if combat.distant_confirmed {
+                    ui.label("Your opponent confirmed");
+                }
+                if attacker == RelSide::Same && ui.button("Cancel attack").clicked() {
+                    to_server.send(ClientMsg::CancelCombat).unwrap();
+                }
+            });
+        });
+}
+
+fn hit_name(hit: &LocalHit) -> String {
+    match hit.target {
+        LocalHitTarget::Card(side, space) => {
+            format!("{} damage to {}", hit.damage, space_name(side, space))
+        }
+        LocalHitTarget::Player(RelSide::Same) => format!("{} damage to you", hit.damage),
+        LocalHitTarget::Player(RelSide::Other) => {
+            format!("{} damage to your opponent", hit.damage)
+        }
+    }
+}
+
+pub fn combat_notice(hits: &[LocalHit]) -> String {
+    if hits.is_empty() {
+        return "Combat is over. Nobody got hurt".to_owned();
+    }
+    let hits: Vec<String> = hits.iter().map(hit_name).collect();
+    format!("Combat is over: {}", hits.join(", "))
+}
+
+fn zone_name(zone: Zone) -> &'static str {
+    match zone {
+        Zone::Hand => "hand",
+        Zone::Deck(DeckType::Main) => "main deck",
+        Zone::Deck(DeckType::Blood) => "blood deck",
+        Zone::Discard => "discard",
+        Zone::Timeline => "timeline",
+        Zone::Row => "row",
+        Zone::Aside => "aside",
+        Zone::Floating => "floating cards",
+    }
+}
+
+/// Toggles for the step actions that run on our turns, and a way to add counter clears.
+fn step_actions(ui: &mut Ui, data: &mut GameData, to_server: &UnboundedSender<ClientMsg>) {
+    let mut hooks = data.state.hooks.clone();
+    let mut removed = None;
+    for (idx, hook) in hooks.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let label = format!("{}: {}", hook.step.get_name(), hook.action.get_name());
+            ui.checkbox(&mut hook.enabled, label);
+            if matches!(hook.action, StepAction::ClearCounter(_)) && ui.button("x").clicked() {
+                removed = Some(idx);
+            }
+        });
+    }
+    if let Some(idx) = removed {
+        hooks.remove(idx);
+    }
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut data.hook_counter);
+        let counter = data.hook_counter.trim().to_uppercase();
+        if ui
+            .add_enabled(!counter.is_empty(), egui::Button::new("Clear on Start"))
+            .clicked()
+        {
+            hooks.push(StepHook {
+                step: TurnStep::Start,
+                action: StepAction::ClearCounter(counter),
+                enabled: true,
+            });
+            data.hook_counter.clear();
+        }
+    });
+    if hooks != data.state.hooks {
+        to_server.send(ClientMsg::SetHooks(hooks)).unwrap();
+    }
+}
+
+fn describe(entry: &LocalHistoryEntry) -> String {
+    let who = match entry.by {
+        RelSide::Same => "You",
+        RelSide::Other => "Opponent",
+    };
+    match &entry.event {
+        LocalHistoryEvent::Roll { sides, results } => {
+            let total: u32 = results.iter().sum();
+            let results: Vec<String> = results.iter().map(u32::to_string).collect();
+            format!(
+                "{who} rolled {}d{sides}: {} (total {total})",
+                results.len(),
+                results.join(", ")
+            )
+        }
+        LocalHistoryEvent::CoinFlip { heads } => {
+            format!("{who} flipped {}", if *heads { "heads" } else { "tails" })
+        }
+        LocalHistoryEvent::RandomCard {
+            owner,
+            zone,
+            position,
+            card,
+        } => {
+            let whose = match (zone, owner) {
+                (Zone::Aside | Zone::Floating, _) => "the",
+                (_, RelSide::Same) => "your",
+                (_, RelSide::Other) => "their",
+            };
+            let card = match card {
+                Hidden::Unhidden(card) => card.name.clone(),
+                Hidden::Hidden => "a hidden card".to_owned(),
+            };
+            format!(
+                "{who} picked card #{} in {whose} {}: {card}",
+                position + 1,
+                zone_name(*zone)
+            )
+        }
+        LocalHistoryEvent::Automated { step, action } => {
+            format!("{}: {who} got to {}", step.get_name(), action.get_name())
+        }
+    }
+}
+
+fn deck_menu(
+    ui: &mut Ui,
+    deck: DeckType,
+    data: &mut GameData,
+    to_server: &UnboundedSender<ClientMsg>,
+) {
+    let mut always = data.state.local_state.always_revealed(deck);
+    if ui.checkbox(&mut always, "Play with top revealed").changed() {
+        to_server
+            .send(ClientMsg::SetPlayWithTopRevealed(deck, always))
+            .unwrap();
+    }
+    if ui.button("Search").clicked() {
+        to_server
+            .send(ClientMsg::RequestSearch(RelSide::Same, deck))
+            .unwrap();
+    }
+    if ui.button("Search their deck").clicked() {
+        to_server
+            .send(ClientMsg::RequestSearch(RelSide::Other, deck))
+            .unwrap();
+    }
+    if ui.button("Reveal top").clicked() {
+        to_server
+            .send(ClientMsg::RevealTop(RelSide::Same, deck))
+            .unwrap();
+    }
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Cards");
+        ui.add(egui::DragValue::new(&mut data.deck_count).range(1..=20));
+    });
+    let n = data.deck_count;
+    if ui.button(format!("Draw {n}")).clicked() {
+        to_server
+            .send(ClientMsg::DrawMany(RelSide::Same, deck, n))
+            .unwrap();
+    }
+    if ui.button(format!("Mill {n}")).clicked() {
+        to_server
+            .send(ClientMsg::Mill(RelSide::Same, deck, n))
+            .unwrap();
+    }
+    for (side, text) in [
+        (RelSide::Same, "Look at top"),
+        (RelSide::Other, "Look at their top"),
+    ] {
+        if ui.button(format!("{text} {n}")).clicked() {
+            to_server
+                .send(ClientMsg::LookAtTop { deck, n, side })
+                .unwrap();
+        }
+    }
+}
+
+pub fn arranged_notice(
+    by: RelSide,
+    side: RelSide,
+    deck: DeckType,
+    counts: ArrangedCounts,
+) -> String {
+    let who = match by {
+        RelSide::Same => "You",
+        RelSide::Other => "Opponent",
+    };
+    let whose = match side {
+        RelSide::Same => "your",
+        RelSide::Other => "their",
+    };
+    let places: Vec<String> = [
+        (counts.top, "on top"),
+        (counts.bottom, "on the bottom"),
+        (counts.hand, "in hand"),
+        (counts.discard, "in the discard"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, place)| format!("{count} {place}"))
+    .collect();
+    format!(
+        "{who} looked at {whose} {} and put {}",
+        zone_name(Zone::Deck(deck)),
+        places.join(", ")
+    )
+}
+
+pub fn looked_notice(by: RelSide, side: RelSide, deck: DeckType, n: usize) -> String {
+    let who = match by {
+        RelSide::Same => "You",
+        RelSide::Other => "Opponent",
+    };
+    let whose = match side {
+        RelSide::Same => "your",
+        RelSide::Other => "their",
+    };
+    format!(
+        "{who} looked at the top {n} of {whose} {}",
+        zone_name(Zone::Deck(deck))
+    )
+}
+
+pub fn searched_notice(by: RelSide, side: RelSide, deck: DeckType, shuffled: bool) -> String {
+    let who = match by {
+        RelSide::Same => "You",
+        RelSide::Other => "Opponent",
+    };
+    let whose = match side {
+        RelSide::Same => "your",
+        RelSide::Other => "their",
+    };
+    let shuffled = if shuffled {
+        "and shuffled it"
+    } else {
+        "without shuffling"
+    };
+    format!(
+        "{who} searched {whose} {} {shuffled}",
+        zone_name(Zone::Deck(deck))
+    )
+}
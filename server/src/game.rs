@@ -0,0 +1,1794 @@
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use shared::Find;
+use shared::{
+    Card, CardId, CardOrName, CardOrNameMut, CardOrNameRef, ClockRules, Clocks, Combat,
+    DEFAULT_SPACES, DeckTo, HEALTH, HitTarget, KnownTo, LookAt, MAX_SPACES, NamedCardId, PlaceFrom,
+    RelSide, RevealTarget, Search, SearchRequest, Space, StepAction, StepHook, TimeoutRule,
+    TurnStep, Zone,
+};
+use shared::{
+    ClientMsg, DeckType, GameOverReason, GameResult, GameState, HistoryEntry, HistoryEvent,
+    LocalMatchScore, LocalState, MAX_DICE, Mulligan, MulliganRule, PlaceTo, PlayerState, ServerErr,
+    ServerMsg, Side, StartRules, Viewer,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use crate::{AuthoredClientMsg, DestinedServerMsg, PlayerId, ToPlayer};
+
+/// Where a card came from. Unlike its position in the game state, this never changes, so
+/// stolen cards can always find their way back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardOrigin {
+    pub owner: Side,
+    /// The deck list this card is part of. None for cards made during the game, and for cards
+    /// from a deck list that was replaced since.
+    pub deck: Option<DeckType>,
+    /// Tokens only exist on the board. They vanish as soon as they go anywhere else.
+    pub token: bool,
+    /// The card this one is a copy of.
+    pub copy_of: Option<CardId>,
+}
+
+/// A best-of-N series of games played in the same room.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub best_of: usize,
+    /// Every finished game, in order.
+    pub results: Vec<GameResult>,
+    /// Who went first in the current game.
+    pub starting_side: Side,
+}
+
+/// League play is best of three.
+pub const DEFAULT_BEST_OF: usize = 3;
+
+impl Match {
+    pub fn new(best_of: usize, starting_side: Side) -> Self {
+        Self {
+            best_of,
+            results: vec![],
+            starting_side,
+        }
+    }
+    pub fn wins(&self, side: Side) -> usize {
+        self.results
+            .iter()
+            .filter(|x| x.winner == Some(side))
+            .count()
+    }
+    /// Whoever won more than half of the games.
+    pub fn winner(&self) -> Option<Side> {
+        [Side::Home, Side::Away]
+            .into_iter()
+            .find(|side| self.wins(*side) > self.best_of / 2)
+    }
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some() || self.results.len() >= self.best_of
+    }
+    pub fn score_for(&self, side: Side) -> LocalMatchScore {
+        LocalMatchScore {
+            best_of: self.best_of,
+            local_wins: self.wins(side),
+            distant_wins: self.wins(side.opposite()),
+            draws: self.results.iter().filter(|x| x.winner.is_none()).count(),
+            winner: self.winner().map(|x| x.relative_to(side)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Game {
+    pub id: String,
+    next_id: usize,
+    pub cards: BTreeMap<CardId, String>,
+    pub origins: BTreeMap<CardId, CardOrigin>,
+    pub home_player: Option<PlayerId>,
+    pub away_player: Option<PlayerId>,
+    pub spectators: Vec<PlayerId>,
+    pub state: GameState,
+    pub current_match: Match,
+    pub start_rules: StartRules,
+    /// Start rules one player proposed, waiting for the other to accept them.
+    pub proposed_rules: Option<(Side, StartRules)>,
+    /// Every random result and automatic action in the room. Unlike the game state, this
+    /// outlives rematches.
+    pub history: Vec<HistoryEntry>,
+    /// What each player wants done for them when their turn reaches a step.
+    pub home_hooks: Vec<StepHook>,
+    pub away_hooks: Vec<StepHook>,
+    /// Only the active player moves the turn along, and only whoever holds priority acts.
+    pub strict: bool,
+    /// None when the room plays without a clock.
+    pub clock_rules: Option<ClockRules>,
+    /// How many spaces each row has.
+    pub spaces: usize,
+    rng: StdRng,
+}
+
+impl Game {
+    pub fn new(id: String) -> Self {
+        Self::from_rng(id, StdRng::from_os_rng())
+    }
+    /// Same as [`Game::new`], but every shuffle in the room is reproducible.
+    pub fn with_seed(id: String, seed: u64) -> Self {
+        Self::from_rng(id, StdRng::seed_from_u64(seed))
+    }
+    fn from_rng(id: String, rng: StdRng) -> Self {
+        Self {
+            id,
+            next_id: 0,
+            cards: BTreeMap::new(),
+            origins: BTreeMap::new(),
+            home_player: None,
+            away_player: None,
+            spectators: vec![],
+            state: GameState::default(),
+            current_match: Match::new(DEFAULT_BEST_OF, Side::Home),
+            start_rules: StartRules::default(),
+            proposed_rules: None,
+            history: vec![],
+            home_hooks: StepHook::defaults(),
+            away_hooks: StepHook::defaults(),
+            strict: false,
+            clock_rules: None,
+            spaces: DEFAULT_SPACES,
+            rng,
+        }
+    }
+    /// Everyone in the room, along with the side they're sitting on.
+    fn everyone(&self) -> Vec<(PlayerId, Option<Side>)> {
+        let home = self.home_player.map(|x| (x, Some(Side::Home)));
+        let away = self.away_player.map(|x| (x, Some(Side::Away)));
+        let spectators = self.spectators.iter().map(|x| (*x, None));
+        home.into_iter().chain(away).chain(spectators).collect()
+    }
+    /// The local state for whoever sits on `side`, match score included.
+    pub fn local_for(&self, side: Option<Side>) -> LocalState {
+        let mut local = self.state.create_local_for(side, &self.cards);
+        local.score = self.current_match.score_for(side.unwrap_or(Side::Home));
+        local.start_rules = self.start_rules;
+        let perspective = side.unwrap_or(Side::Home);
+        local.proposed_start_rules = self
+            .proposed_rules
+            .map(|(by, rules)| (by.relative_to(perspective), rules));
+        local.history = self
+            .history
+            .iter()
+            .map(|x| x.to_local(Viewer::from(side), &self.cards))
+            .collect();
+        local.hooks = self.hooks(side.unwrap_or(Side::Home)).clone();
+        local.strict = self.strict;
+        local.clock_rules = self.clock_rules;
+        let board = local
+            .local_row
+            .iter_mut()
+            .chain(local.distant_row.iter_mut())
+            .chain(local.local_state.timeline.iter_mut())
+            .chain(local.distant_state.timeline.iter_mut())
+            .chain(local.floating_cards.iter_mut().map(|(x, _)| x));
+        for card in board {
+            card.token = self.is_token(card.id);
+            for attached in &mut card.attached {
+                attached.token = self.is_token(attached.id);
+            }
+        }
+        local
+    }
+    pub fn hooks(&self, side: Side) -> &Vec<StepHook> {
+        match side {
+            Side::Home => &self.home_hooks,
+            Side::Away => &self.away_hooks,
+        }
+    }
+    pub fn hooks_mut(&mut self, side: Side) -> &mut Vec<StepHook> {
+        match side {
+            Side::Home => &mut self.home_hooks,
+            Side::Away => &mut self.away_hooks,
+        }
+    }
+    /// Runs the step actions of whoever's turn it is for the step that just began, and logs
+    /// each one.
+    fn run_hooks(&mut self, out: &mut Vec<DestinedServerMsg>) {
+        let side = self.state.turn.whose;
+        let step = self.state.turn.step;
+        let actions: Vec<StepAction> = self
+            .hooks(side)
+            .iter()
+            .filter(|x| x.enabled && x.step == step)
+            .map(|x| x.action.clone())
+            .collect();
+        for action in actions {
+            match &action {
+                StepAction::Draw(deck) => {
+                    self.draw(side, side, *deck, 1);
+                }
+                StepAction::GainBlood => self.state.get_state_mut(side).blood += 1,
+                StepAction::ClearCounter(counter) => {
+                    for card in self.state.board_mut(side) {
+                        card.counters.remove(counter);
+                    }
+                }
+                StepAction::ClearStatus(status) => {
+                    for card in self.state.board_mut(side) {
+                        card.status.set(*status, false);
+                    }
+                }
+            }
+            let event = HistoryEvent::Automated { step, action };
+            self.record(HistoryEntry { by: side, event }, out);
+        }
+    }
+    /// Hands priority to `side`. Whoever gives it up gets their increment.
+    fn set_priority(&mut self, side: Side) {
+        let previous = self.state.priority;
+        match (&mut self.state.turn.clocks, self.clock_rules) {
+            (Some(clocks), Some(rules)) if side != previous => {
+                *clocks.get_mut(previous) += rules.increment;
+            }
+            _ => {}
+        }
+        self.state.priority = side;
+    }
+    /// Moves the turn to `step`, or over to the other player on [`TurnStep::Switch`]. Moving
+    /// on from the opening hands begins the step afresh, even if it's the same one.
+    fn set_step(&mut self, step: TurnStep, out: &mut Vec<DestinedServerMsg>) {
+        let previous = self.state.turn;
+        let opening = self.state.mulligan.take().is_some();
+        if step == TurnStep::Switch {
+            self.state.turn.whose = self.state.turn.whose.opposite();
+            self.state.turn.step = TurnStep::Start;
+        } else {
+            self.state.turn.step = step;
+        }
+        self.set_priority(self.state.turn.whose);
+        if opening
+            || (self.state.turn.whose, self.state.turn.step) != (previous.whose, previous.step)
+        {
+            self.run_hooks(out);
+        }
+        // Leaving the combat step drops whatever attack wasn't confirmed
+        if self.state.turn.step != TurnStep::Combat {
+            self.state.combat = None;
+        }
+    }
+    /// Runs down the clock of whoever holds priority by `elapsed`. The room calls this every
+    /// second or so.
+    pub fn tick(&mut self, elapsed: Duration) -> Vec<DestinedServerMsg> {
+        let mut out = vec![];
+        let (Some(side), Some(rules)) = (self.state.running_clock(), self.clock_rules) else {
+            return out;
+        };
+        let Some(clocks) = &mut self.state.turn.clocks else {
+            return out;
+        };
+        let left = clocks.get_mut(side);
+        let had_time = !left.is_zero();
+        *left = left.saturating_sub(elapsed);
+        let out_of_time = left.is_zero();
+        let clocks = *clocks;
+        // A clock that already ran out only matters again if the rules act on it
+        if !had_time && rules.timeout == TimeoutRule::Warn {
+            return out;
+        }
+        if had_time {
+            for (player, viewer) in self.everyone() {
+                let perspective = viewer.unwrap_or(Side::Home);
+                let local = clocks.to_local(perspective, Some(side));
+                out.push(ServerMsg::Clocks(local).to_player(player));
+            }
+        }
+        if !out_of_time {
+            return out;
+        }
+
+        if had_time {
+            for (player, viewer) in self.everyone() {
+                let perspective = viewer.unwrap_or(Side::Home);
+                out.push(ServerMsg::OutOfTime(side.relative_to(perspective)).to_player(player));
+            }
+        }
+        match rules.timeout {
+            TimeoutRule::Warn => return out,
+            TimeoutRule::AutoPass => {
+                if side == self.state.turn.whose {
+                    self.set_step(TurnStep::Switch, &mut out);
+                } else {
+                    self.set_priority(self.state.turn.whose);
+                }
+            }
+            TimeoutRule::Lose => {
+                let result = GameResult {
+                    winner: Some(side.opposite()),
+                    reason: GameOverReason::OutOfTime,
+                };
+                self.end(result, &mut out);
+            }
+        }
+        self.update_all(&mut out);
+        out
+    }
+    fn update_all(&self, out: &mut Vec<DestinedServerMsg>) {
+        for (player, side) in self.everyone() {
+            out.push(ServerMsg::UpdateState(Box::new(self.local_for(side))).to_player(player));
+        }
+    }
+    /// Ends the game and tells everyone how it went. Spectators see it from home's side.
+    fn end(&mut self, result: GameResult, out: &mut Vec<DestinedServerMsg>) {
+        self.state.result = Some(result);
+        self.state.draw_offer = None;
+        self.current_match.results.push(result);
+        for (player, side) in self.everyone() {
+            let local = result.relative_to(side.unwrap_or(Side::Home));
+            out.push(
+                ServerMsg::GameOver {
+                    winner: local.winner,
+                    reason: local.reason,
+                }
+                .to_player(player),
+            );
+        }
+    }
+    /// Deals the damage of the current combat. Creatures left without health go to their
+    /// owner's discard.
+    fn resolve_combat(&mut self, out: &mut Vec<DestinedServerMsg>) {
+        let Some(combat) = self.state.combat.take() else {
+            return;
+        };
+        let hits = combat.hits(&self.state);
+        for hit in &hits {
+            match hit.target {
+                HitTarget::Player(side) => {
+                    let state = self.state.get_state_mut(side);
+                    state.health = state.health.saturating_sub(hit.damage);
+                }
+                HitTarget::Card(side, space) => {
+                    let row = self.state.get_row_mut(side);
+                    let Some(card) = &mut row[space] else {
+                        continue;
+                    };
+                    let health = card.counters.entry(HEALTH.to_owned()).or_default();
+                    *health = health.saturating_sub_unsigned(hit.damage);
+                    if *health <= 0 {
+                        let card = row[space].take().unwrap();
+                        let owner = self.origins[&card.id].owner;
+                        self.place(card.into(), PlaceTo::Discard(RelSide::Same), owner);
+                    }
+                }
+            }
+        }
+        for (player, side) in self.everyone() {
+            let perspective = side.unwrap_or(Side::Home);
+            let local = hits.iter().map(|x| x.relative_to(perspective)).collect();
+            out.push(ServerMsg::CombatResolved(local).to_player(player));
+        }
+        if let Some(result) = self.state.check_health() {
+            self.end(result, out);
+        }
+    }
+    /// Adds a random result to the history and tells everyone about it.
+    fn record(&mut self, entry: HistoryEntry, out: &mut Vec<DestinedServerMsg>) {
+        for (player, side) in self.everyone() {
+            let local = entry.to_local(Viewer::from(side), &self.cards);
+            out.push(ServerMsg::History(local).to_player(player));
+        }
+        self.history.push(entry);
+    }
+    /// Sends the player the deck they're searching, if they're searching one.
+    fn send_search(&self, side: Side, out: &mut Vec<DestinedServerMsg>) {
+        let (Some(player), Some(search)) =
+            (self.get_player(side), self.state.get_state(side).searching)
+        else {
+            return;
+        };
+        out.push(
+            ServerMsg::BeginSearch {
+                side: search.owner.relative_to(side),
+                deck: search.deck,
+                cards: self.state.create_search_for(side, &self.cards),
+            }
+            .to_player(player),
+        );
+    }
+    /// Shows a player the top of a deck, and tells everyone else how many cards they saw.
+    fn look_at_top(
+        &mut self,
+        by: Side,
+        owner: Side,
+        deck: DeckType,
+        n: usize,
+        out: &mut Vec<DestinedServerMsg>,
+    ) -> Result<(), ServerErr> {
+        let side = owner.relative_to(by);
+        let cards = self.state.get_state(owner).get_deck(deck);
+        if cards.is_empty() {
+            return Err(ServerErr::NoCardsIn(side, Zone::Deck(deck)));
+        }
+        if n >= cards.len() {
+            return Err(ServerErr::LookAtWholeDeck);
+        }
+        let cards: Vec<CardId> = cards.iter().rev().take(n).copied().collect();
+
+        let named: Vec<NamedCardId> = cards
+            .iter()
+            .map(|id| NamedCardId {
+                name: self.cards[id].clone(),
+                id: *id,
+            })
+            .collect();
+        self.state.get_state_mut(by).looking_at = Some(LookAt { owner, deck, cards });
+        for (player, viewer) in self.everyone() {
+            if viewer == Some(by) {
+                out.push(
+                    ServerMsg::LookAtTop {
+                        side,
+                        deck,
+                        cards: named.clone(),
+                    }
+                    .to_player(player),
+                );
+                continue;
+            }
+            let perspective = viewer.unwrap_or(Side::Home);
+            out.push(
+                ServerMsg::LookedAtTop {
+                    by: by.relative_to(perspective),
+                    side: owner.relative_to(perspective),
+                    deck,
+                    n,
+                }
+                .to_player(player),
+            );
+        }
+        Ok(())
+    }
+    fn shuffle(&mut self, side: Side, deck: DeckType) {
+        let state = self.state.get_state_mut(side);
+        state.hide_top(deck);
+        state
+            .get_deck_mut(deck)
+            .make_contiguous()
+            .shuffle(&mut self.rng);
+    }
+    pub fn is_desolate(&self) -> bool {
+        self.home_player.is_none() && self.away_player.is_none() && self.spectators.is_empty()
+    }
+    pub fn is_token(&self, card: CardId) -> bool {
+        self.origins.get(&card).is_some_and(|x| x.token)
+    }
+    /// Puts a card somewhere. Tokens that would leave the board vanish instead, and
+    /// attachments fall off into their owners' discards unless their host goes to a space.
+    fn place(&mut self, card: CardOrName, to: PlaceTo, local_side: Side) {
+        let card = match card {
+            CardOrName::Card(mut card) if !matches!(to, PlaceTo::Space(..)) => {
+                for attached in std::mem::take(&mut card.attached) {
+                    let owner = self.origins[&attached.id].owner;
+                    self.place(attached.into(), PlaceTo::Discard(RelSide::Same), owner);
+                }
+                CardOrName::Card(card)
+            }
+            card => card,
+        };
+        if self.is_token(card.id()) && !to.is_board() {
+            return;
+        }
+        let card = match (card, &to) {
+            (CardOrName::Card(mut card), PlaceTo::Space(_, _, true)) => {
+                card.id = self.disguise(card.id);
+                CardOrName::Card(card)
+            }
+            (CardOrName::Name(id), PlaceTo::Space(_, _, true)) => {
+                CardOrName::Name(self.disguise(id))
+            }
+            (card, _) => card,
+        };
+        self.state.push_card(card, to, local_side);
+    }
+    /// Gives a card a new id. Cards get one whenever they go face down, so nobody can tell
+    /// which card it is by the id it had while face up.
+    fn disguise(&mut self, id: CardId) -> CardId {
+        let name = self.cards[&id].clone();
+        let origin = self.origins.remove(&id).unwrap();
+        self.add_card(name, origin)
+    }
+    /// Spaces have to exist, attachments need a host to go on, and a card can't be attached to
+    /// itself.
+    fn place_error(
+        &self,
+        from: Option<PlaceFrom>,
+        to: &PlaceTo,
+        local_side: Side,
+    ) -> Option<ServerErr> {
+        let (PlaceTo::Space(side, space, _) | PlaceTo::Attached(side, space)) = *to else {
+            return None;
+        };
+        let row = self.state.get_row(side.make_real(local_side));
+        if !row.has_space(space) {
+            return Some(ServerErr::NoSuchSpace(side, space));
+        }
+        let PlaceTo::Attached(..) = to else {
+            return None;
+        };
+        let empty = row.get(space).is_none();
+        let is_host = matches!(from, Some(PlaceFrom::Space(s, x)) if s == side && x == space);
+        (empty || is_host).then_some(ServerErr::NothingToAttachTo(side, space))
+    }
+    /// Gives both rows `spaces` spaces, or leaves them alone if a card is in the way.
+    fn resize_rows(&mut self, spaces: usize) -> Result<(), (Side, Space)> {
+        let old = self.state.get_row(Side::Home).len();
+        let home = self.state.get_row_mut(Side::Home);
+        home.resize(spaces).map_err(|x| (Side::Home, x))?;
+        if let Err(space) = self.state.get_row_mut(Side::Away).resize(spaces) {
+            // Growing back never fails.
+            let _ = self.state.get_row_mut(Side::Home).resize(old);
+            return Err((Side::Away, space));
+        }
+        self.spaces = spaces;
+        Ok(())
+    }
+    fn add_card(&mut self, card: String, origin: CardOrigin) -> CardId {
+        let id = CardId(self.next_id);
+        self.cards.insert(id, card);
+        self.origins.insert(id, origin);
+        self.next_id += 1;
+        id
+    }
+    /// The deck list the player registered, in the order it was given.
+    pub fn deck_list(&self, side: Side, deck: DeckType) -> Vec<CardId> {
+        let origin = CardOrigin {
+            owner: side,
+            deck: Some(deck),
+            token: false,
+            copy_of: None,
+        };
+        self.origins
+            .iter()
+            .filter(|(_, x)| **x == origin)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+    /// End of game cleanup. Resets the whole game state and puts every card back into its
+    /// owner's deck list. Cards made during the game are left out.
+    pub fn gather_decks(&mut self) {
+        let mut state = GameState::with_spaces(self.spaces);
+        for side in [Side::Home, Side::Away] {
+            *state.get_state_mut(side) = PlayerState {
+                main_deck: self.deck_list(side, DeckType::Main).into(),
+                blood_deck: self.deck_list(side, DeckType::Blood).into(),
+                ..PlayerState::default()
+            };
+        }
+        self.state = state;
+    }
+    /// Starts the next game with the same decks, shuffled. Whoever went second last time
+    /// goes first. Once the match is decided, this starts a new one of the same length.
+    pub fn rematch(&mut self, out: &mut Vec<DestinedServerMsg>) {
+        let starting_side = self.current_match.starting_side.opposite();
+        self.deal(starting_side, out);
+    }
+    /// Deals the current game again, with a random player going first.
+    pub fn start_game(&mut self, out: &mut Vec<DestinedServerMsg>) {
+        let starting_side = if self.rng.random_bool(0.5) {
+            Side::Home
+        } else {
+            Side::Away
+        };
+        self.deal(starting_side, out);
+    }
+    /// Whether a dealt game is still being played.
+    pub fn in_progress(&self) -> bool {
+        self.state.started && self.state.result.is_none()
+    }
+    /// Rebuilds and shuffles both decks, deals opening hands and starts the mulligan step.
+    /// Without mulligans, the first turn starts right away.
+    fn deal(&mut self, starting_side: Side, out: &mut Vec<DestinedServerMsg>) {
+        if self.current_match.is_over() {
+            self.current_match = Match::new(self.current_match.best_of, starting_side);
+        } else {
+            self.current_match.starting_side = starting_side;
+        }
+        self.gather_decks();
+        for side in [Side::Home, Side::Away] {
+            self.shuffle(side, DeckType::Main);
+            self.shuffle(side, DeckType::Blood);
+            self.draw_opening_hand(side, self.start_rules.main_cards);
+        }
+        self.state.turn.whose = starting_side;
+        self.state.turn.clocks = self.clock_rules.map(Clocks::new);
+        self.state.priority = starting_side;
+        self.state.started = true;
+        self.state.mulligan = Some(Mulligan::default());
+        if self.start_rules.mulligan == MulliganRule::Disabled {
+            self.set_step(TurnStep::Start, out);
+        }
+    }
+    /// Moves up to `n` cards from the top of `owner`'s deck into `drawer`'s hand. Returns how
+    /// many were actually drawn.
+    fn draw(&mut self, drawer: Side, owner: Side, deck: DeckType, n: usize) -> usize {
+        let mut drawn = 0;
+        while drawn < n {
+            let Some(card) = self.state.get_state_mut(owner).pop_top(deck) else {
+                break;
+            };
+            self.state.get_state_mut(drawer).hand.push(card);
+            drawn += 1;
+        }
+        drawn
+    }
+    fn draw_opening_hand(&mut self, side: Side, main_cards: usize) {
+        self.draw(side, side, DeckType::Main, main_cards);
+        self.draw(side, side, DeckType::Blood, self.start_rules.blood_cards);
+    }
+    /// Shuffles the hand back into the decks its cards came from and draws a new one.
+    fn mulligan(&mut self, side: Side) {
+        let hand = std::mem::take(&mut self.state.get_state_mut(side).hand);
+        self.state.get_state_mut(side).revealed.clear();
+        for card in hand {
+            let deck = self.origins[&card].deck.unwrap_or(DeckType::Main);
+            self.state
+                .get_state_mut(side)
+                .get_deck_mut(deck)
+                .push_back(card);
+        }
+        self.shuffle(side, DeckType::Main);
+        self.shuffle(side, DeckType::Blood);
+
+        let Some(mulligan) = &mut self.state.mulligan else {
+            return;
+        };
+        let status = mulligan.get_mut(side);
+        status.mulligans += 1;
+        let main_cards = match self.start_rules.mulligan {
+            MulliganRule::OneFewer => self.start_rules.main_cards.saturating_sub(status.mulligans),
+            MulliganRule::Disabled | MulliganRule::Free => self.start_rules.main_cards,
+        };
+        self.draw_opening_hand(side, main_cards);
+    }
+    pub fn get_player(&self, side: Side) -> Option<PlayerId> {
+        match side {
+            Side::Home => self.home_player,
+            Side::Away => self.away_player,
+        }
+    }
+    pub fn get_side(&self, id: PlayerId) -> Option<Side> {
+        if self.home_player.is_some_and(|x| x == id) {
+            Some(Side::Home)
+        } else if self.away_player.is_some_and(|x| x == id) {
+            Some(Side::Away)
+        } else {
+            None
+        }
+    }
+}
+
+/// Applies a single client message to the room and returns everything that has to be sent
+/// back out because of it. This does no IO, so it can be driven directly from tests.
+pub fn apply(game: &mut Game, msg: AuthoredClientMsg) -> Vec<DestinedServerMsg> {
+    let mut out = vec![];
+    let author_side = game.get_side(msg.author);
+    if game.strict
+        && msg.message.needs_priority()
+        && author_side.is_some_and(|x| x != game.state.priority)
+    {
+        out.push(ServerErr::NoPriority.to_player(msg.author));
+        return out;
+    }
+    match msg.message {
+        ClientMsg::Draw(deck_owner, which_deck) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let owner = deck_owner.make_real(local_side);
+            if game.draw(local_side, owner, which_deck, 1) == 0 {
+                return out;
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::DrawMany(deck_owner, which_deck, n) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let owner = deck_owner.make_real(local_side);
+            if game.draw(local_side, owner, which_deck, n) == 0 {
+                return out;
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Mill(deck_owner, which_deck, n) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let state = game.state.get_state_mut(deck_owner.make_real(local_side));
+            for _ in 0..n {
+                let Some(card) = state.pop_top(which_deck) else {
+                    break;
+                };
+                state.discard.push(card);
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::RevealTop(deck_owner, which_deck) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let state = game.state.get_state_mut(deck_owner.make_real(local_side));
+            let Some(top) = state.get_deck(which_deck).back().copied() else {
+                out.push(
+                    ServerErr::NoCardsIn(deck_owner, Zone::Deck(which_deck)).to_player(msg.author),
+                );
+                return out;
+            };
+            state.get_top_reveal_mut(which_deck).card = Some(top);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetPlayWithTopRevealed(which_deck, revealed) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let state = game.state.get_state_mut(local_side);
+            state.get_top_reveal_mut(which_deck).always = revealed;
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Move { from, to } => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            if let Some(err) = game.place_error(Some(from), &to, local_side) {
+                out.push(err.to_player(msg.author));
+                return out;
+            }
+
+            let card: Option<CardOrName> = game.state.pop_card(from, local_side);
+
+            let Some(card) = card else {
+                out.push(ServerErr::NoCardIn(from).to_player(msg.author));
+                return out;
+            };
+
+            game.place(card, to, local_side);
+
+            for side in [Side::Home, Side::Away] {
+                game.send_search(side, &mut out);
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Shuffle(deck) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            game.shuffle(local_side, deck);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::RequestSearch(rel_side, deck) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let search = Search {
+                owner: rel_side.make_real(local_side),
+                deck,
+            };
+            match rel_side {
+                RelSide::Same => {
+                    game.state.get_state_mut(local_side).searching = Some(search);
+                    game.send_search(local_side, &mut out);
+                }
+                RelSide::Other => {
+                    if game.get_player(search.owner).is_none() {
+                        out.push(ServerErr::NoPlayerInSide(search.owner).to_player(msg.author));
+                        return out;
+                    }
+                    game.state.search_request = Some(SearchRequest {
+                        by: local_side,
+                        search,
+                        top: None,
+                    });
+                    game.update_all(&mut out);
+                }
+            }
+        }
+        ClientMsg::AnswerSearch(allowed) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(request) = game
+                .state
+                .search_request
+                .filter(|x| x.search.owner == local_side)
+            else {
+                out.push(ServerErr::NoSearchRequest.to_player(msg.author));
+                return out;
+            };
+
+            game.state.search_request = None;
+            if allowed {
+                let Search { owner, deck } = request.search;
+                match request.top {
+                    Some(n) => {
+                        // The deck might have changed while they were asking
+                        let looked = game.look_at_top(request.by, owner, deck, n, &mut out);
+                        if let (Err(err), Some(player)) = (looked, game.get_player(request.by)) {
+                            out.push(err.to_player(player));
+                        }
+                    }
+                    None => {
+                        game.state.get_state_mut(request.by).searching = Some(request.search);
+                        game.send_search(request.by, &mut out);
+                    }
+                }
+            } else if let Some(player) = game.get_player(request.by) {
+                out.push(ServerErr::SearchDenied.to_player(player));
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Update => {
+            out.push(
+                ServerMsg::UpdateState(Box::new(game.local_for(author_side))).to_player(msg.author),
+            );
+        }
+        ClientMsg::SetDeck(deck, contents) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            for origin in game.origins.values_mut() {
+                if origin.owner == local_side && origin.deck == Some(deck) {
+                    origin.deck = None;
+                }
+            }
+
+            let origin = CardOrigin {
+                owner: local_side,
+                deck: Some(deck),
+                token: false,
+                copy_of: None,
+            };
+            let contents = contents
+                .into_iter()
+                .map(|x| game.add_card(x, origin))
+                .collect();
+
+            let state = game.state.get_state_mut(local_side);
+
+            match deck {
+                DeckType::Blood => state.blood_deck = contents,
+                DeckType::Main => state.main_deck = contents,
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::PlayAs => {
+            let mut author_side = author_side;
+            game.spectators.find_remove(msg.author);
+            if game.home_player.is_none() {
+                game.home_player = Some(msg.author);
+                author_side = Some(Side::Home);
+            } else if game.away_player.is_none() {
+                game.away_player = Some(msg.author);
+                author_side = Some(Side::Away);
+            } else {
+                game.spectators.push(msg.author);
+                out.push(ServerErr::GameIsFull.to_player(msg.author));
+            }
+
+            out.push(
+                ServerMsg::UpdateState(Box::new(game.local_for(author_side))).to_player(msg.author),
+            );
+        }
+        ClientMsg::CreateRoom(..) => {
+            out.push(
+                ServerErr::AlreadyInGame {
+                    action: msg.message.get_name().to_string(),
+                }
+                .to_player(msg.author),
+            );
+        }
+        ClientMsg::JoinRoom(ref room) => {
+            if *room == game.id {
+                game.spectators.push(msg.author);
+                out.push(
+                    ServerMsg::JoinedRoom(Box::new(game.local_for(author_side)))
+                        .to_player(msg.author),
+                );
+                out.push(
+                    ServerMsg::UpdateState(Box::new(game.local_for(None))).to_player(msg.author),
+                );
+                return out;
+            }
+            out.push(
+                ServerErr::AlreadyInGame {
+                    action: msg.message.get_name().to_string(),
+                }
+                .to_player(msg.author),
+            );
+        }
+        ClientMsg::AddCounter(target, counter, amount) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(counters) = game.state.get_counters_mut(target, local_side) else {
+                out.push(ServerErr::NoCountersOn(target).to_player(msg.author));
+                return out;
+            };
+
+            let num = counters.entry(counter).or_insert(0);
+            *num = num.saturating_add(amount);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::CreateCounter(target, counter) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(counters) = game.state.get_counters_mut(target, local_side) else {
+                out.push(ServerErr::NoCountersOn(target).to_player(msg.author));
+                return out;
+            };
+
+            counters.entry(counter).or_insert(0);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetCounter(targets, counter, value) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            // Either every target gets the counter or none does
+            if let Some(target) = targets
+                .iter()
+                .find(|x| game.state.get_counters_mut(**x, local_side).is_none())
+            {
+                out.push(ServerErr::NoCountersOn(*target).to_player(msg.author));
+                return out;
+            }
+
+            for target in targets {
+                if let Some(counters) = game.state.get_counters_mut(target, local_side) {
+                    counters.insert(counter.clone(), value);
+                }
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetStatus(from, status, value) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(CardOrNameMut::Card(card)) = game.state.get_card_mut(from, local_side) else {
+                out.push(ServerErr::NoCardIn(from).to_player(msg.author));
+                return out;
+            };
+
+            card.status.set(status, value);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::RemoveCounter(target, counter) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(counters) = game.state.get_counters_mut(target, local_side) else {
+                out.push(ServerErr::NoCountersOn(target).to_player(msg.author));
+                return out;
+            };
+
+            counters.remove(&counter);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::FinishSearch { shuffle } => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(search) = game.state.get_state_mut(local_side).searching.take() else {
+                out.push(ServerErr::NotSearching.to_player(msg.author));
+                return out;
+            };
+
+            if shuffle {
+                game.shuffle(search.owner, search.deck);
+            }
+            for (player, side) in game.everyone() {
+                let perspective = side.unwrap_or(Side::Home);
+                out.push(
+                    ServerMsg::Searched {
+                        by: local_side.relative_to(perspective),
+                        side: search.owner.relative_to(perspective),
+                        deck: search.deck,
+                        shuffled: shuffle,
+                    }
+                    .to_player(player),
+                );
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::LeaveRoom => {
+            println!("Player is found to have left and the room is processing that.");
+            match author_side {
+                Some(Side::Home) => game.home_player = None,
+                Some(Side::Away) => game.away_player = None,
+                None => (),
+            }
+
+            game.spectators.find_remove(msg.author);
+        }
+        ClientMsg::AddBlood(rel_side, up) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let side = rel_side.make_real(local_side);
+
+            let blood = game.state.get_state_mut(side).blood;
+            if up {
+                game.state.get_state_mut(side).blood = blood.saturating_add(1);
+            } else {
+                game.state.get_state_mut(side).blood = blood.saturating_sub(1);
+            }
+            game.update_all(&mut out);
+        }
+        ClientMsg::TurnSet(step) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if game.strict && game.state.turn.whose != local_side {
+                out.push(ServerErr::NotYourTurn.to_player(msg.author));
+                return out;
+            }
+            if game.state.mulligan.is_some() {
+                out.push(ServerErr::StillMulliganing.to_player(msg.author));
+                return out;
+            }
+
+            game.set_step(step, &mut out);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::AddHealth(rel_side, delta) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let state = game.state.get_state_mut(rel_side.make_real(local_side));
+            state.health = state.health.saturating_add_signed(delta);
+            if let Some(result) = game.state.check_health() {
+                game.end(result, &mut out);
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::CreateCard(card) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let card = game.add_card(
+                card,
+                CardOrigin {
+                    owner: local_side,
+                    deck: None,
+                    token: false,
+                    copy_of: None,
+                },
+            );
+            let state = game.state.get_state_mut(local_side);
+            state.hand.push(card);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::CreateToken(name, to) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if !to.is_board() {
+                out.push(ServerErr::TokenOffBoard.to_player(msg.author));
+                return out;
+            }
+            if let Some(err) = game.place_error(None, &to, local_side) {
+                out.push(err.to_player(msg.author));
+                return out;
+            }
+
+            let origin = CardOrigin {
+                owner: local_side,
+                deck: None,
+                token: true,
+                copy_of: None,
+            };
+            let id = game.add_card(name, origin);
+            game.place(Card::from_id(id, false).into(), to, local_side);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::CopyCard { from, to } => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if !to.is_board() {
+                out.push(ServerErr::TokenOffBoard.to_player(msg.author));
+                return out;
+            }
+            if let Some(err) = game.place_error(None, &to, local_side) {
+                out.push(err.to_player(msg.author));
+                return out;
+            }
+            let (original, counters) = match game.state.get_card(from, local_side) {
+                Some(CardOrNameRef::Card(card)) => (card.id, card.counters.clone()),
+                Some(CardOrNameRef::Name(id)) => (*id, HashMap::new()),
+                None => {
+                    out.push(ServerErr::NoCardIn(from).to_player(msg.author));
+                    return out;
+                }
+            };
+            if game.state.sees_card(from, local_side) != Some(true) {
+                out.push(ServerErr::CannotCopyHidden(from).to_player(msg.author));
+                return out;
+            }
+            let origin = CardOrigin {
+                owner: local_side,
+                deck: None,
+                token: true,
+                copy_of: Some(original),
+            };
+            let id = game.add_card(game.cards[&original].clone(), origin);
+            let copy = Card {
+                counters,
+                ..Card::from_id(id, false)
+            };
+            game.place(copy.into(), to, local_side);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::ReturnToOwner(from) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(card) = game.state.pop_card(from, local_side) else {
+                out.push(ServerErr::NoCardIn(from).to_player(msg.author));
+                return out;
+            };
+
+            let owner = game.origins[&card.id()].owner;
+            game.place(card, PlaceTo::Hand, owner);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Flip(from) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(CardOrNameMut::Card(card)) = game.state.get_card_mut(from, local_side) else {
+                out.push(ServerErr::NoCardIn(from).to_player(msg.author));
+                return out;
+            };
+
+            *card = card.clone().flipped(!card.backside, local_side);
+            if card.backside {
+                let id = card.id;
+                let id = game.disguise(id);
+                if let Some(CardOrNameMut::Card(card)) = game.state.get_card_mut(from, local_side) {
+                    card.id = id;
+                }
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Peek(from) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let owner = match game.state.get_card(from, local_side) {
+                Some(CardOrNameRef::Card(card)) => game.origins[&card.id].owner,
+                _ => {
+                    out.push(ServerErr::NoCardIn(from).to_player(msg.author));
+                    return out;
+                }
+            };
+            if owner != local_side && from.side() != Some(RelSide::Same) {
+                out.push(ServerErr::CannotPeek(from).to_player(msg.author));
+                return out;
+            }
+            let Some(CardOrNameMut::Card(card)) = game.state.get_card_mut(from, local_side) else {
+                return out;
+            };
+
+            card.known_to.insert(local_side);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Reveal { cards, to } => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            for from in &cards {
+                let owner = match game.state.get_card(*from, local_side) {
+                    Some(CardOrNameRef::Card(card)) => game.origins[&card.id].owner,
+                    Some(CardOrNameRef::Name(id)) => game.origins[id].owner,
+                    None => {
+                        out.push(ServerErr::NoCardIn(*from).to_player(msg.author));
+                        return out;
+                    }
+                };
+                if owner != local_side && from.side() != Some(RelSide::Same) {
+                    out.push(ServerErr::CannotReveal(*from).to_player(msg.author));
+                    return out;
+                }
+            }
+
+            let mut revealed = vec![];
+            for from in cards {
+                let id = match game.state.get_card_mut(from, local_side) {
+                    Some(CardOrNameMut::Card(card)) => {
+                        card.known_to.insert(local_side);
+                        card.known_to.insert(local_side.opposite());
+                        card.id
+                    }
+                    Some(CardOrNameMut::Name(id)) => *id,
+                    None => continue,
+                };
+                if let PlaceFrom::Hand(_) = from {
+                    game.state
+                        .get_state_mut(local_side)
+                        .reveal(id, to.visibility());
+                }
+                revealed.push(NamedCardId {
+                    name: game.cards[&id].clone(),
+                    id,
+                });
+            }
+
+            for (player, side) in game.everyone() {
+                let shown = match side {
+                    Some(side) => side != local_side,
+                    None => to == RevealTarget::Everyone,
+                };
+                if shown {
+                    out.push(
+                        ServerMsg::Revealed {
+                            by: local_side.relative_to(side.unwrap_or(Side::Home)),
+                            cards: revealed.clone(),
+                        }
+                        .to_player(player),
+                    );
+                }
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::DeclareAttack(attacks) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if game.state.turn.whose != local_side {
+                out.push(ServerErr::NotYourTurn.to_player(msg.author));
+                return out;
+            }
+            if game.state.turn.step != TurnStep::Combat {
+                out.push(ServerErr::NotCombatStep.to_player(msg.author));
+                return out;
+            }
+            let attacking = game.state.get_row(local_side);
+            let defending = game.state.get_row(local_side.opposite());
+            if attacks.is_empty() || !Combat::valid_attacks(&attacks, attacking, defending) {
+                out.push(ServerErr::BadAttack.to_player(msg.author));
+                return out;
+            }
+
+            game.state.combat = Some(Combat::new(local_side, attacks));
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::DeclareBlocks(blocks) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(combat) = &mut game.state.combat else {
+                out.push(ServerErr::NoCombat.to_player(msg.author));
+                return out;
+            };
+            if combat.defender() != local_side {
+                out.push(ServerErr::NotYourTurn.to_player(msg.author));
+                return out;
+            }
+            let defending = match local_side {
+                Side::Home => &game.state.home_row,
+                Side::Away => &game.state.away_row,
+            };
+            if !combat.valid_blocks(&blocks, defending) {
+                out.push(ServerErr::BadBlock.to_player(msg.author));
+                return out;
+            }
+
+            combat.blocks = blocks;
+            combat.confirmed = KnownTo::default();
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::ConfirmCombat => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(combat) = &mut game.state.combat else {
+                out.push(ServerErr::NoCombat.to_player(msg.author));
+                return out;
+            };
+
+            combat.confirmed.insert(local_side);
+            if combat.confirmed.contains(local_side.opposite()) {
+                game.resolve_combat(&mut out);
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::CancelCombat => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if game
+                .state
+                .combat
+                .as_ref()
+                .is_none_or(|x| x.attacker != local_side)
+            {
+                out.push(ServerErr::NoCombat.to_player(msg.author));
+                return out;
+            }
+
+            game.state.combat = None;
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Concede => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if game.state.result.is_some() {
+                out.push(ServerErr::GameIsOver.to_player(msg.author));
+                return out;
+            }
+            if !game.state.started {
+                out.push(ServerErr::GameNotStarted.to_player(msg.author));
+                return out;
+            }
+
+            let result = GameResult {
+                winner: Some(local_side.opposite()),
+                reason: GameOverReason::Conceded,
+            };
+            game.end(result, &mut out);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetMatchLength(best_of) => {
+            if author_side.is_none() {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            }
+            if best_of == 0 {
+                out.push(ServerErr::MatchTooShort.to_player(msg.author));
+                return out;
+            }
+            if !game.current_match.results.is_empty() {
+                out.push(ServerErr::MatchUnderway.to_player(msg.author));
+                return out;
+            }
+
+            game.current_match.best_of = best_of;
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::OfferDraw => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if game.state.result.is_some() {
+                out.push(ServerErr::GameIsOver.to_player(msg.author));
+                return out;
+            }
+            if !game.state.started {
+                out.push(ServerErr::GameNotStarted.to_player(msg.author));
+                return out;
+            }
+
+            game.state.draw_offer = Some(local_side);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::AcceptDraw => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if game.state.result.is_some() {
+                out.push(ServerErr::GameIsOver.to_player(msg.author));
+                return out;
+            }
+            if !game.state.started {
+                out.push(ServerErr::GameNotStarted.to_player(msg.author));
+                return out;
+            }
+            if game.state.draw_offer != Some(local_side.opposite()) {
+                out.push(ServerErr::NoDrawOffered.to_player(msg.author));
+                return out;
+            }
+
+            let result = GameResult {
+                winner: None,
+                reason: GameOverReason::AgreedDraw,
+            };
+            game.end(result, &mut out);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Rematch => {
+            if author_side.is_none() {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            }
+            if game.state.result.is_none() {
+                out.push(ServerErr::GameNotOver.to_player(msg.author));
+                return out;
+            }
+
+            game.rematch(&mut out);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Roll { sides, count } => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if sides == 0 || count == 0 || count > MAX_DICE {
+                out.push(ServerErr::BadRoll.to_player(msg.author));
+                return out;
+            }
+
+            let results = (0..count)
+                .map(|_| game.rng.random_range(1..=sides))
+                .collect();
+            let entry = HistoryEntry {
+                by: local_side,
+                event: HistoryEvent::Roll { sides, results },
+            };
+            game.record(entry, &mut out);
+        }
+        ClientMsg::FlipCoin => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            let entry = HistoryEntry {
+                by: local_side,
+                event: HistoryEvent::CoinFlip {
+                    heads: game.rng.random_bool(0.5),
+                },
+            };
+            game.record(entry, &mut out);
+        }
+        ClientMsg::PickRandom(rel_side, zone) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let owner = rel_side.make_real(local_side);
+            let cards = game.state.zone_cards(owner, zone);
+            if cards.is_empty() {
+                out.push(ServerErr::NoCardsIn(rel_side, zone).to_player(msg.author));
+                return out;
+            }
+
+            let position = game.rng.random_range(0..cards.len());
+            let (card, visibility) = cards[position];
+            let entry = HistoryEntry {
+                by: local_side,
+                event: HistoryEvent::RandomCard {
+                    owner,
+                    zone,
+                    position,
+                    card,
+                    visibility,
+                },
+            };
+            game.record(entry, &mut out);
+        }
+        ClientMsg::LookAtTop { deck, n, side } => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let owner = side.make_real(local_side);
+            match side {
+                RelSide::Same => {
+                    if let Err(err) = game.look_at_top(local_side, owner, deck, n, &mut out) {
+                        out.push(err.to_player(msg.author));
+                    }
+                }
+                RelSide::Other => {
+                    if game.get_player(owner).is_none() {
+                        out.push(ServerErr::NoPlayerInSide(owner).to_player(msg.author));
+                        return out;
+                    }
+                    let len = game.state.get_state(owner).get_deck(deck).len();
+                    if len == 0 {
+                        out.push(
+                            ServerErr::NoCardsIn(side, Zone::Deck(deck)).to_player(msg.author),
+                        );
+                        return out;
+                    }
+                    if n >= len {
+                        out.push(ServerErr::LookAtWholeDeck.to_player(msg.author));
+                        return out;
+                    }
+                    game.state.search_request = Some(SearchRequest {
+                        by: local_side,
+                        search: Search { owner, deck },
+                        top: Some(n),
+                    });
+                    game.update_all(&mut out);
+                }
+            }
+        }
+        ClientMsg::ArrangeTop(arrangement) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(look) = game.state.get_state(local_side).looking_at.clone() else {
+                out.push(ServerErr::NotLooking.to_player(msg.author));
+                return out;
+            };
+
+            let mut arranged: Vec<CardId> = arrangement.cards().collect();
+            let mut looked = look.cards.clone();
+            arranged.sort();
+            looked.sort();
+            if arranged != looked {
+                out.push(ServerErr::BadArrangement.to_player(msg.author));
+                return out;
+            }
+            // Anything that happened to the deck since might have moved the cards
+            let deck = game.state.get_state(look.owner).get_deck(look.deck);
+            if !deck.iter().rev().take(look.cards.len()).eq(&look.cards) {
+                game.state.get_state_mut(local_side).looking_at = None;
+                out.push(ServerErr::DeckChangedSinceLook.to_player(msg.author));
+                return out;
+            }
+
+            game.state.get_state_mut(local_side).looking_at = None;
+            game.state.get_state_mut(look.owner).hide_top(look.deck);
+            let deck = game.state.get_state_mut(look.owner).get_deck_mut(look.deck);
+            deck.retain(|x| !looked.contains(x));
+            for card in arrangement.top.iter().rev() {
+                let to = PlaceTo::Deck(DeckTo::Top, RelSide::Same, look.deck);
+                game.state.push_card((*card).into(), to, look.owner);
+            }
+            for card in &arrangement.bottom {
+                let to = PlaceTo::Deck(DeckTo::Bottom, RelSide::Same, look.deck);
+                game.state.push_card((*card).into(), to, look.owner);
+            }
+            for card in &arrangement.hand {
+                game.state
+                    .push_card((*card).into(), PlaceTo::Hand, local_side);
+            }
+            for card in &arrangement.discard {
+                let to = PlaceTo::Discard(RelSide::Same);
+                game.state.push_card((*card).into(), to, look.owner);
+            }
+
+            for (player, side) in game.everyone() {
+                let perspective = side.unwrap_or(Side::Home);
+                out.push(
+                    ServerMsg::TopArranged {
+                        by: local_side.relative_to(perspective),
+                        side: look.owner.relative_to(perspective),
+                        deck: look.deck,
+                        counts: arrangement.counts(),
+                    }
+                    .to_player(player),
+                );
+            }
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetStrict(strict) => {
+            if author_side.is_none() {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            }
+            if game.in_progress() {
+                out.push(ServerErr::GameInProgress.to_player(msg.author));
+                return out;
+            }
+
+            game.strict = strict;
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetClock(rules) => {
+            if author_side.is_none() {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            }
+            if game.in_progress() {
+                out.push(ServerErr::GameInProgress.to_player(msg.author));
+                return out;
+            }
+
+            game.clock_rules = rules;
+            game.state.turn.clocks = rules.map(Clocks::new);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetSpaces(spaces) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if spaces == 0 || spaces > MAX_SPACES {
+                out.push(ServerErr::BadSpaceCount(spaces).to_player(msg.author));
+                return out;
+            }
+
+            if let Err((side, space)) = game.resize_rows(spaces) {
+                let side = side.relative_to(local_side);
+                out.push(ServerErr::SpaceInUse(side, space).to_player(msg.author));
+                return out;
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::PassPriority => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            game.set_priority(local_side.opposite());
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetHooks(hooks) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            *game.hooks_mut(local_side) = hooks;
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::SetStartRules(rules) => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+
+            if game.get_player(local_side.opposite()).is_none() {
+                game.start_rules = rules;
+                game.proposed_rules = None;
+            } else {
+                game.proposed_rules = Some((local_side, rules));
+            }
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::AcceptStartRules => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some((by, rules)) = game.proposed_rules else {
+                out.push(ServerErr::NoStartRulesProposed.to_player(msg.author));
+                return out;
+            };
+            if by == local_side {
+                out.push(ServerErr::NoStartRulesProposed.to_player(msg.author));
+                return out;
+            }
+
+            game.start_rules = rules;
+            game.proposed_rules = None;
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::StartGame => {
+            if author_side.is_none() {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            }
+            if game.in_progress() {
+                out.push(ServerErr::GameInProgress.to_player(msg.author));
+                return out;
+            }
+
+            game.start_game(&mut out);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::Mulligan => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            if game.state.mulligan.is_none_or(|x| x.get(local_side).kept) {
+                out.push(ServerErr::NotMulliganing.to_player(msg.author));
+                return out;
+            }
+
+            game.mulligan(local_side);
+
+            game.update_all(&mut out);
+        }
+        ClientMsg::KeepHand => {
+            let Some(local_side) = author_side else {
+                out.push(ServerErr::NotInSide.to_player(msg.author));
+                return out;
+            };
+            let Some(mulligan) = &mut game.state.mulligan else {
+                out.push(ServerErr::NotMulliganing.to_player(msg.author));
+                return out;
+            };
+
+            mulligan.get_mut(local_side).kept = true;
+            let mulligan = *mulligan;
+            let done = [Side::Home, Side::Away]
+                .into_iter()
+                .all(|side| mulligan.get(side).kept || game.get_player(side).is_none());
+            if done {
+                game.set_step(TurnStep::Start, &mut out);
+            }
+
+            game.update_all(&mut out);
+        }
+    }
+    out
+}
@@ -0,0 +1,63 @@
+use shared::{ClientMsg, ServerErr, ServerMsg};
+use std::net::SocketAddr;
+
+mod game;
+mod net;
+
+pub use game::{CardOrigin, DEFAULT_BEST_OF, Game, Match, apply};
+pub use net::{Games, player_task};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlayerId(pub SocketAddr);
+
+#[derive(Debug)]
+pub struct AuthoredClientMsg {
+    pub author: PlayerId,
+    pub message: ClientMsg,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Destination {
+    Player(PlayerId),
+}
+
+#[derive(Clone, Debug)]
+pub struct DestinedServerMsg {
+    pub author: Destination,
+    pub message: Result<ServerMsg, ServerErr>,
+}
+
+pub trait FromPlayer {
+    fn sent_by(self, player: PlayerId) -> AuthoredClientMsg;
+}
+
+impl FromPlayer for ClientMsg {
+    fn sent_by(self, player: PlayerId) -> AuthoredClientMsg {
+        AuthoredClientMsg {
+            author: player,
+            message: self,
+        }
+    }
+}
+
+pub trait ToPlayer {
+    fn to_player(self, player: PlayerId) -> DestinedServerMsg;
+}
+
+impl ToPlayer for ServerMsg {
+    fn to_player(self, player: PlayerId) -> DestinedServerMsg {
+        DestinedServerMsg {
+            author: Destination::Player(player),
+            message: Ok(self),
+        }
+    }
+}
+
+impl ToPlayer for ServerErr {
+    fn to_player(self, player: PlayerId) -> DestinedServerMsg {
+        DestinedServerMsg {
+            author: Destination::Player(player),
+            message: Err(self),
+        }
+    }
+}
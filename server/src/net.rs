@@ -0,0 +1,318 @@
+use serde_json::to_string_pretty;
+use std::sync::Weak;
+use std::time::Duration;
+use std::{collections::HashMap, sync::Arc};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior, interval};
+use tokio::{
+    select,
+    sync::{
+        RwLock,
+        broadcast::{self},
+        mpsc,
+    },
+};
+
+use futures::{SinkExt, StreamExt, future::OptionFuture};
+use shared::{ClientMsg, ServerErr, ServerMsg};
+use tokio_websockets::{Message, WebSocketStream};
+
+use crate::{
+    AuthoredClientMsg, Destination, DestinedServerMsg, FromPlayer, Game, PlayerId, ToPlayer, apply,
+};
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct GameId(String);
+
+type Global<T> = Arc<RwLock<T>>;
+/// Using a Weak pointer since all the player tasks will be holding Arc pointers anyways.
+/// The Games struct should not be able to hold the GameHandle. Once all player tasks leave
+/// the game room should be dropped. This ensures that.
+pub type Games = Global<HashMap<GameId, Weak<GameHandle>>>;
+
+pub struct GameHandle {
+    to_game: mpsc::UnboundedSender<AuthoredClientMsg>,
+    game_broadcast: broadcast::Sender<DestinedServerMsg>,
+}
+
+struct PlayerGameHandle {
+    _game: Arc<GameHandle>,
+    to_game: mpsc::UnboundedSender<AuthoredClientMsg>,
+    game_broadcast: broadcast::Receiver<DestinedServerMsg>,
+}
+
+/// This function returns a message to be sent to the client
+async fn after_stream_next(
+    player_id: PlayerId,
+    msg: Message,
+    games: &Games,
+    current_game_handle: &mut Option<PlayerGameHandle>,
+) -> Option<(Result<ServerMsg, ServerErr>, Option<JoinHandle<()>>)> {
+    if msg.is_close() {
+        return None;
+    }
+    if msg.is_ping() {
+        return None;
+    }
+    let msg = msg.as_text()?;
+    let msg = match serde_json::from_str::<ClientMsg>(msg) {
+        Ok(a) => a,
+        Err(_) => todo!("Couldn't parse msg from client"),
+    };
+
+    if msg.is_game_action() {
+        if let Some(current_game_handle) = current_game_handle {
+            current_game_handle
+                .to_game
+                .send(msg.sent_by(player_id))
+                .unwrap();
+        } else {
+            return Some((
+                Err(ServerErr::NotInGame {
+                    action: msg.get_name().to_owned(),
+                }),
+                None,
+            ));
+        }
+
+        return None;
+    }
+
+    match msg {
+        ClientMsg::JoinRoom(string) => {
+            let games = games.read().await;
+            let game_handle = games.get(&GameId(string.clone())).and_then(|x| x.upgrade());
+            *current_game_handle = game_handle.map(|x| PlayerGameHandle {
+                to_game: x.to_game.clone(),
+                game_broadcast: x.game_broadcast.subscribe(),
+                _game: x,
+            });
+            drop(games);
+            match current_game_handle {
+                Some(game_handle) => {
+                    game_handle
+                        .to_game
+                        .send(ClientMsg::JoinRoom(string).sent_by(player_id))
+                        .unwrap();
+                }
+                None => return Some((Err(ServerErr::RoomDoesntExist(string)), None)),
+            }
+
+            None
+        }
+        ClientMsg::CreateRoom(room) => {
+            let (to_game, from_player) = mpsc::unbounded_channel();
+            let (to_players, from_game) = broadcast::channel(16);
+            let handle = GameHandle {
+                to_game: to_game.clone(),
+                game_broadcast: to_players.clone(),
+            };
+            let mut games = games.write().await;
+            if games
+                .get(&GameId(room.clone()))
+                .and_then(|x| x.upgrade())
+                .is_some()
+            {
+                return Some((Err(ServerErr::RoomAlreadyExist), None));
+            }
+            let handle = Arc::new(handle);
+            let weak_handle = Arc::downgrade(&handle);
+            games.insert(GameId(room.clone()), weak_handle);
+            let task = tokio::spawn(room_task(room, player_id, from_player, to_players));
+            *current_game_handle = Some(PlayerGameHandle {
+                to_game,
+                game_broadcast: from_game,
+                _game: handle,
+            });
+
+            Some((Ok(ServerMsg::RoomCreated), Some(task)))
+        }
+        ClientMsg::RequestSearch(..) => None,
+        ClientMsg::Draw(..) => None,
+        ClientMsg::Move { .. } => None,
+        ClientMsg::Shuffle(..) => None,
+        ClientMsg::Update => None,
+        ClientMsg::SetDeck(..) => None,
+        ClientMsg::PlayAs => None,
+        ClientMsg::AddCounter(..) => None,
+        ClientMsg::CreateCounter(..) => None,
+        ClientMsg::SetCounter(..) => None,
+        ClientMsg::RemoveCounter(..) => None,
+        ClientMsg::SetStatus(..) => None,
+        ClientMsg::FinishSearch { .. } => None,
+        ClientMsg::AnswerSearch(..) => None,
+        ClientMsg::LeaveRoom => None,
+        ClientMsg::AddBlood(..) => None,
+        ClientMsg::TurnSet(..) => None,
+        ClientMsg::AddHealth(..) => None,
+        ClientMsg::CreateCard(..) => None,
+        ClientMsg::CreateToken(..) => None,
+        ClientMsg::CopyCard { .. } => None,
+        ClientMsg::ReturnToOwner(..) => None,
+        ClientMsg::Flip(..) => None,
+        ClientMsg::Peek(..) => None,
+        ClientMsg::Reveal { .. } => None,
+        ClientMsg::Concede => None,
+        ClientMsg::SetMatchLength(..) => None,
+        ClientMsg::OfferDraw => None,
+        ClientMsg::AcceptDraw => None,
+        ClientMsg::Rematch => None,
+        ClientMsg::SetStartRules(..) => None,
+        ClientMsg::AcceptStartRules => None,
+        ClientMsg::StartGame => None,
+        ClientMsg::Mulligan => None,
+        ClientMsg::KeepHand => None,
+        ClientMsg::Roll { .. } => None,
+        ClientMsg::FlipCoin => None,
+        ClientMsg::PickRandom(..) => None,
+        ClientMsg::LookAtTop { .. } => None,
+        ClientMsg::ArrangeTop(..) => None,
+        ClientMsg::DrawMany(..) => None,
+        ClientMsg::Mill(..) => None,
+        ClientMsg::RevealTop(..) => None,
+        ClientMsg::SetPlayWithTopRevealed(..) => None,
+        ClientMsg::SetHooks(..) => None,
+        ClientMsg::SetStrict(..) => None,
+        ClientMsg::PassPriority => None,
+        ClientMsg::SetClock(..) => None,
+        ClientMsg::SetSpaces(..) => None,
+        ClientMsg::DeclareAttack(..) => None,
+        ClientMsg::DeclareBlocks(..) => None,
+        ClientMsg::ConfirmCombat => None,
+        ClientMsg::CancelCombat => None,
+    }
+}
+
+pub async fn player_task<S: AsyncRead + AsyncWrite + Unpin>(
+    player_id: PlayerId,
+    mut ws_stream: WebSocketStream<S>,
+    games: Games,
+) -> Vec<JoinHandle<()>> {
+    let mut current_game_handle: Option<PlayerGameHandle> = None;
+    let mut tasks = vec![];
+    loop {
+        let broadcast_fut: OptionFuture<_> = match &mut current_game_handle {
+            Some(a) => Some(a.game_broadcast.recv()).into(),
+            None => None.into(),
+        };
+        select! {
+            Some(msg) = broadcast_fut => {
+                match msg {
+                    Ok(msg) => {
+                        let Destination::Player(recv_id) = msg.author;
+                        if player_id != recv_id {
+                            continue
+                        }
+
+                        ws_stream.send(Message::text(to_string_pretty(&msg.message).unwrap())).await.unwrap();
+                    },
+                    Err(RecvError::Closed) => {
+                        current_game_handle = None;
+                    },
+                    Err(RecvError::Lagged(..)) => match &mut current_game_handle {
+                        Some(a) => {
+                            a.to_game.send(ClientMsg::Update.sent_by(player_id)).unwrap()
+                        },
+                        None => continue,
+                    },
+                }
+            },
+            msg = ws_stream.next() => {
+                match msg {
+                    Some(Ok(msg)) => {
+                        if msg.is_close() {
+                            eprintln!("Received close message");
+                            match &current_game_handle {
+                                Some(x) => {
+                                    eprintln!("player left room on close message");
+                                    x.to_game.send(ClientMsg::LeaveRoom.sent_by(player_id)).unwrap();
+                                },
+                                None => {
+                                    eprintln!("Player left room");
+                                },
+                            }
+                            break;
+                        }
+                        let Some((result, task)) = after_stream_next(player_id, msg, &games, &mut current_game_handle).await else { continue };
+                        ws_stream.send(Message::text(to_string_pretty(&result).unwrap())).await.unwrap();
+                        if let Some(task) = task {
+                            tasks.push(task);
+                        }
+                    },
+                    None => {
+                        match &current_game_handle {
+                            Some(x) => {
+                                eprintln!("Player safely disconnected while in room");
+                                x.to_game.send(ClientMsg::LeaveRoom.sent_by(player_id)).unwrap();
+                            },
+                            None => {
+                                eprintln!("Player safely disconnedted outside of room");
+                            },
+                        }
+                        break
+                    },
+                    Some(Err(err)) => match &current_game_handle {
+                        Some(x) => {
+                            eprintln!("Player connection failed with {err:#?}");
+                            x.to_game.send(ClientMsg::LeaveRoom.sent_by(player_id)).unwrap();
+                            break;
+                        },
+                        None => {
+                            eprintln!("2 Player connection failed with {err:#?}");
+                            break;
+                        },
+                    },
+                }
+            },
+        }
+    }
+
+    eprintln!("Player task died");
+
+    tasks
+}
+
+async fn room_task(
+    id: String,
+    creator: PlayerId,
+    mut from_player: mpsc::UnboundedReceiver<AuthoredClientMsg>,
+    to_players: broadcast::Sender<DestinedServerMsg>,
+) {
+    let mut game = Game::new(id);
+
+    to_players
+        .send(ServerMsg::JoinedRoom(Box::new(game.local_for(None))).to_player(creator))
+        .unwrap();
+    // Keeps the clocks running even when nobody is doing anything
+    let mut ticks = interval(Duration::from_secs(1));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_tick = Instant::now();
+    loop {
+        select! {
+            msg = from_player.recv() => match msg {
+                Some(msg) => {
+                    let leaving = matches!(msg.message, ClientMsg::LeaveRoom);
+                    for msg in apply(&mut game, msg) {
+                        to_players.send(msg).unwrap();
+                    }
+
+                    if leaving && game.is_desolate() {
+                        println!("{game:#?}");
+                        println!("Room is desolate.");
+                        break;
+                    }
+                }
+                None => panic!("cave.ogg"),
+            },
+            now = ticks.tick() => {
+                for msg in game.tick(now - last_tick) {
+                    // Fails when nobody is subscribed right now, and then there's nobody to tell
+                    let _ = to_players.send(msg);
+                }
+                last_tick = now;
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+// Not every test file uses every helper in here.
+#![allow(dead_code)]
+
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+
+use cassowary_server::{
+    Destination, DestinedServerMsg, FromPlayer, Game, Games, PlayerId, apply, player_task,
+};
+use futures::{SinkExt, StreamExt};
+use shared::{ClientMsg, LocalState, ServerErr, ServerMsg};
+use tokio::io::DuplexStream;
+use tokio_websockets::{ClientBuilder, Message, ServerBuilder, WebSocketStream};
+
+pub const ROOM: &str = "test";
+
+fn fake_player(port: u16) -> PlayerId {
+    PlayerId(SocketAddr::from(([127, 0, 0, 1], port)))
+}
+
+/// Drives a single room synchronously through [`apply`], remembering what every player was
+/// last told.
+pub struct Table {
+    pub game: Game,
+    next_port: u16,
+    views: HashMap<PlayerId, LocalState>,
+    inbox: HashMap<PlayerId, Vec<Result<ServerMsg, ServerErr>>>,
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            game: Game::with_seed(ROOM.to_owned(), seed),
+            next_port: 1,
+            views: HashMap::new(),
+            inbox: HashMap::new(),
+        }
+    }
+
+    /// Joins the room as a spectator.
+    pub fn connect(&mut self) -> PlayerId {
+        let player = fake_player(self.next_port);
+        self.next_port += 1;
+        self.send(player, ClientMsg::JoinRoom(ROOM.to_owned()));
+        player
+    }
+
+    /// Joins the room and takes the first free side, like the client does.
+    pub fn sit(&mut self) -> PlayerId {
+        let player = self.connect();
+        self.send(player, ClientMsg::PlayAs);
+        player
+    }
+
+    pub fn send(&mut self, player: PlayerId, msg: ClientMsg) -> Vec<DestinedServerMsg> {
+        let out = apply(&mut self.game, msg.sent_by(player));
+        self.deliver(&out);
+        out
+    }
+
+    /// Lets `elapsed` pass on the clocks, like the room task does every second.
+    pub fn tick(&mut self, elapsed: Duration) -> Vec<DestinedServerMsg> {
+        let out = self.game.tick(elapsed);
+        self.deliver(&out);
+        out
+    }
+
+    fn deliver(&mut self, out: &[DestinedServerMsg]) {
+        for msg in out {
+            let Destination::Player(to) = msg.author;
+            match &msg.message {
+                Ok(ServerMsg::UpdateState(state) | ServerMsg::JoinedRoom(state)) => {
+                    self.views.insert(to, *state.clone());
+                }
+                Ok(ServerMsg::Clocks(clocks)) => {
+                    if let Some(view) = self.views.get_mut(&to) {
+                        view.turn.clocks = Some(*clocks);
+                    }
+                }
+                _ => {}
+            }
+            self.inbox.entry(to).or_default().push(msg.message.clone());
+        }
+    }
+
+    pub fn script(&mut self, steps: impl IntoIterator<Item = (PlayerId, ClientMsg)>) {
+        for (player, msg) in steps {
+            self.send(player, msg);
+        }
+    }
+
+    /// The last state the player was sent.
+    pub fn view(&self, player: PlayerId) -> &LocalState {
+        self.views
+            .get(&player)
+            .expect("player never received a state")
+    }
+
+    /// Everything the player was sent since the last call.
+    pub fn take_inbox(&mut self, player: PlayerId) -> Vec<Result<ServerMsg, ServerErr>> {
+        self.inbox.remove(&player).unwrap_or_default()
+    }
+
+    pub fn take_errors(&mut self, player: PlayerId) -> Vec<ServerErr> {
+        self.take_inbox(player)
+            .into_iter()
+            .filter_map(Result::err)
+            .collect()
+    }
+}
+
+/// A server whose connections are in-memory pipes instead of sockets.
+pub struct TestServer {
+    games: Games,
+    next_port: u16,
+}
+
+impl TestServer {
+    pub fn new() -> Self {
+        Self {
+            games: Games::default(),
+            next_port: 1,
+        }
+    }
+
+    pub async fn connect(&mut self) -> TestClient {
+        let (client_io, server_io) = tokio::io::duplex(1 << 16);
+        let player = fake_player(self.next_port);
+        self.next_port += 1;
+
+        let games = self.games.clone();
+        tokio::spawn(async move {
+            let (_request, ws_stream) = ServerBuilder::new().accept(server_io).await.unwrap();
+            player_task(player, ws_stream, games).await
+        });
+
+        let (ws, _) = ClientBuilder::new()
+            .uri("ws://localhost/")
+            .unwrap()
+            .connect_on(client_io)
+            .await
+            .unwrap();
+
+        TestClient { ws }
+    }
+}
+
+pub struct TestClient {
+    ws: WebSocketStream<DuplexStream>,
+}
+
+impl TestClient {
+    pub async fn send(&mut self, msg: ClientMsg) {
+        let msg = serde_json::to_string(&msg).unwrap();
+        self.ws.send(Message::text(msg)).await.unwrap();
+    }
+
+    pub async fn recv(&mut self) -> Result<ServerMsg, ServerErr> {
+        loop {
+            let msg = tokio::time::timeout(Duration::from_secs(5), self.ws.next())
+                .await
+                .expect("timed out waiting for the server")
+                .expect("server closed the connection")
+                .unwrap();
+            let Some(text) = msg.as_text() else { continue };
+            return serde_json::from_str(text).unwrap();
+        }
+    }
+
+    /// Skips messages until a state matching `pred` arrives.
+    pub async fn recv_state_until(&mut self, pred: impl Fn(&LocalState) -> bool) -> LocalState {
+        loop {
+            match self.recv().await {
+                Ok(ServerMsg::UpdateState(state) | ServerMsg::JoinedRoom(state))
+                    if pred(&state) =>
+                {
+                    return *state;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    pub async fn recv_err(&mut self) -> ServerErr {
+        loop {
+            if let Err(err) = self.recv().await {
+                return err;
+            }
+        }
+    }
+}
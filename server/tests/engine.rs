@@ -0,0 +1,2292 @@
+mod common;
+
+use std::time::Duration;
+
+use cassowary_server::{Destination, DestinedServerMsg, PlayerId};
+use common::Table;
+use shared::{
+    ArrangedCounts, Attack, AttackTarget, Block, CardId, ClientMsg, ClockRules, CounterTarget,
+    DEFENSE, DeckTo, DeckType, GameOverReason, HEALTH, Hidden, LocalCard, LocalDeckTop,
+    LocalHistoryEntry, LocalHistoryEvent, LocalHit, LocalHitTarget, MulliganRule, NamedCardId,
+    POWER, PlaceFrom, PlaceTo, RelSide, RevealTarget, STARTING_HEALTH, ServerErr, ServerMsg, Side,
+    Space, StartRules, Status, StepAction, StepHook, TimeoutRule, TopArrangement, TurnStep, Zone,
+};
+
+fn deck(cards: &[&str]) -> ClientMsg {
+    ClientMsg::SetDeck(
+        DeckType::Main,
+        cards.iter().map(|x| x.to_string()).collect(),
+    )
+}
+
+/// Keeps rematches from dealing anything, so decks can be checked whole.
+fn no_opening_hand() -> ClientMsg {
+    ClientMsg::SetStartRules(StartRules {
+        main_cards: 0,
+        blood_cards: 0,
+        mulligan: MulliganRule::Disabled,
+    })
+}
+
+fn name(card: &LocalCard) -> Option<&str> {
+    match &card.name {
+        Hidden::Hidden => None,
+        Hidden::Unhidden(name) => Some(name),
+    }
+}
+
+#[test]
+fn players_take_free_sides_in_order() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    let third = table.sit();
+
+    assert_eq!(table.game.get_side(home), Some(Side::Home));
+    assert_eq!(table.game.get_side(away), Some(Side::Away));
+    assert_eq!(table.game.get_side(third), None);
+    assert!(matches!(
+        table.take_errors(third).as_slice(),
+        [ServerErr::GameIsFull]
+    ));
+}
+
+#[test]
+fn spectators_cannot_act() {
+    let mut table = Table::new();
+    let spectator = table.connect();
+    table.send(spectator, ClientMsg::Draw(RelSide::Same, DeckType::Main));
+
+    assert!(matches!(
+        table.take_errors(spectator).as_slice(),
+        [ServerErr::NotInSide]
+    ));
+}
+
+#[test]
+fn draw_move_to_space_and_add_counter() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, deck(&["Daemon", "BloodFlask"])),
+        (home, ClientMsg::Draw(RelSide::Same, DeckType::Main)),
+    ]);
+
+    let hand = &table.view(home).hand;
+    assert_eq!(hand.len(), 1);
+    assert_eq!(hand[0].name, "BloodFlask");
+    assert!(table.view(away).hand.is_empty());
+
+    let card = hand[0].id;
+    let space = PlaceFrom::Space(RelSide::Same, Space(0));
+    table.script([
+        (
+            home,
+            ClientMsg::Move {
+                from: PlaceFrom::Hand(card),
+                to: PlaceTo::Space(RelSide::Same, Space(0), false),
+            },
+        ),
+        (
+            home,
+            ClientMsg::AddCounter(CounterTarget::Card(space), "HP".to_owned(), 1),
+        ),
+        (
+            home,
+            ClientMsg::AddCounter(CounterTarget::Card(space), "HP".to_owned(), 1),
+        ),
+    ]);
+
+    assert!(table.view(home).hand.is_empty());
+    let home_view = table.view(home).local_row[Space(0)].as_ref().unwrap();
+    let away_view = table.view(away).distant_row[Space(0)].as_ref().unwrap();
+    for card in [home_view, away_view] {
+        assert_eq!(name(card), Some("BloodFlask"));
+        assert_eq!(card.counters.get("HP"), Some(&2));
+    }
+    assert!(table.view(away).local_row[Space(0)].is_none());
+}
+
+#[test]
+fn moving_a_missing_card_is_an_error() {
+    let mut table = Table::new();
+    let home = table.sit();
+    table.take_inbox(home);
+
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Space(RelSide::Same, Space(2)),
+            to: PlaceTo::Hand,
+        },
+    );
+
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoCardIn(PlaceFrom::Space(
+            RelSide::Same,
+            Space(2)
+        ))]
+    ));
+}
+
+#[test]
+fn face_down_cards_are_known_only_to_whoever_played_them() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    let spectator = table.connect();
+
+    table.send(home, ClientMsg::CreateCard("Daemon".to_owned()));
+    let card = table.view(home).hand[0].id;
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(card),
+            to: PlaceTo::Space(RelSide::Same, Space(1), true),
+        },
+    );
+
+    let home_view = table.view(home).local_row[Space(1)].as_ref().unwrap();
+    let away_view = table.view(away).distant_row[Space(1)].as_ref().unwrap();
+    let spectator_view = table.view(spectator).local_row[Space(1)].as_ref().unwrap();
+    assert!(home_view.face_down && away_view.face_down);
+    assert_eq!(name(home_view), Some("Daemon"));
+    assert_eq!(name(away_view), None);
+    assert_eq!(name(spectator_view), None);
+}
+
+#[test]
+fn cards_get_a_new_id_when_they_go_face_down() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, ClientMsg::CreateCard("Daemon".to_owned()));
+    let card = table.view(home).hand[0].id;
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(card),
+            to: PlaceTo::Space(RelSide::Same, Space(0), false),
+        },
+    );
+    let seen = table.view(away).distant_row[Space(0)].clone().unwrap().id;
+    assert_eq!(seen, card);
+
+    table.send(
+        home,
+        ClientMsg::Flip(PlaceFrom::Space(RelSide::Same, Space(0))),
+    );
+    let hidden = table.view(away).distant_row[Space(0)].clone().unwrap();
+    assert_eq!(name(&hidden), None);
+    assert_ne!(hidden.id, seen);
+
+    // Moving it face down somewhere else doesn't give it away either
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Space(RelSide::Same, Space(0)),
+            to: PlaceTo::Space(RelSide::Same, Space(1), true),
+        },
+    );
+    let moved = table.view(away).distant_row[Space(1)].clone().unwrap();
+    assert_ne!(moved.id, hidden.id);
+    let known = table.view(home).local_row[Space(1)].clone().unwrap();
+    assert_eq!(name(&known), Some("Daemon"));
+}
+
+#[test]
+fn peeking_at_or_revealing_the_opponents_face_down_cards_is_refused() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(away, ClientMsg::CreateCard("Daemon".to_owned()));
+    let card = table.view(away).hand[0].id;
+    table.send(
+        away,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(card),
+            to: PlaceTo::Space(RelSide::Same, Space(0), true),
+        },
+    );
+
+    let from = PlaceFrom::Space(RelSide::Other, Space(0));
+    table.script([
+        (home, ClientMsg::Peek(from)),
+        (
+            home,
+            ClientMsg::Reveal {
+                cards: vec![from],
+                to: RevealTarget::Everyone,
+            },
+        ),
+    ]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::CannotPeek(_), ServerErr::CannotReveal(_)]
+    ));
+    let card = table.view(home).distant_row[Space(0)].clone().unwrap();
+    assert_eq!(name(&card), None);
+}
+
+#[test]
+fn peek_reveal_and_flip() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    let spectator = table.connect();
+
+    table.send(home, ClientMsg::CreateCard("Daemon".to_owned()));
+    let card = table.view(home).hand[0].id;
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(card),
+            to: PlaceTo::Space(RelSide::Other, Space(0), true),
+        },
+    );
+    let away_card = |table: &Table| {
+        let card = table.view(away).local_row[Space(0)].clone().unwrap();
+        name(&card).map(str::to_owned)
+    };
+    let spectator_card = |table: &Table| {
+        let card = table.view(spectator).distant_row[Space(0)].clone().unwrap();
+        name(&card).map(str::to_owned)
+    };
+    assert_eq!(away_card(&table), None);
+
+    table.send(
+        away,
+        ClientMsg::Peek(PlaceFrom::Space(RelSide::Same, Space(0))),
+    );
+    assert_eq!(away_card(&table).as_deref(), Some("Daemon"));
+    assert_eq!(spectator_card(&table), None);
+
+    table.send(
+        home,
+        ClientMsg::Flip(PlaceFrom::Space(RelSide::Other, Space(0))),
+    );
+    assert_eq!(spectator_card(&table).as_deref(), Some("Daemon"));
+
+    table.send(
+        away,
+        ClientMsg::Flip(PlaceFrom::Space(RelSide::Same, Space(0))),
+    );
+    assert_eq!(away_card(&table).as_deref(), Some("Daemon"));
+    let home_view = table.view(home).distant_row[Space(0)].clone().unwrap();
+    assert_eq!(name(&home_view), None);
+
+    table.send(
+        away,
+        ClientMsg::Reveal {
+            cards: vec![PlaceFrom::Space(RelSide::Same, Space(0))],
+            to: RevealTarget::Opponent,
+        },
+    );
+    let home_view = table.view(home).distant_row[Space(0)].clone().unwrap();
+    assert_eq!(name(&home_view), Some("Daemon"));
+    assert_eq!(spectator_card(&table), None);
+}
+
+#[test]
+fn seeded_shuffles_are_reproducible() {
+    let cards: Vec<String> = (0..20).map(|x| x.to_string()).collect();
+    let cards: Vec<&str> = cards.iter().map(String::as_str).collect();
+
+    let shuffled = |seed| {
+        let mut table = Table::with_seed(seed);
+        let home = table.sit();
+        table.send(home, deck(&cards));
+        table.send(home, ClientMsg::Shuffle(DeckType::Main));
+        table.game.state.home_state.main_deck.clone()
+    };
+
+    assert_eq!(shuffled(7), shuffled(7));
+    assert_ne!(shuffled(7), shuffled(8));
+}
+
+#[test]
+fn spectators_do_not_see_any_hand() {
+    let mut table = Table::new();
+    let home = table.sit();
+    table.sit();
+    let spectator = table.connect();
+
+    table.send(home, ClientMsg::CreateCard("Daemon".to_owned()));
+
+    assert_eq!(table.view(home).hand.len(), 1);
+    assert!(table.view(spectator).hand.is_empty());
+}
+
+#[test]
+fn searching_the_blood_deck_shows_the_blood_deck() {
+    let mut table = Table::new();
+    let home = table.sit();
+    table.script([
+        (home, deck(&["Daemon"])),
+        (
+            home,
+            ClientMsg::SetDeck(DeckType::Blood, ["BloodFlask".to_owned()].into()),
+        ),
+    ]);
+    table.take_inbox(home);
+
+    table.send(
+        home,
+        ClientMsg::RequestSearch(RelSide::Same, DeckType::Blood),
+    );
+
+    let searched: Vec<_> = table
+        .take_inbox(home)
+        .into_iter()
+        .filter_map(|x| match x {
+            Ok(ServerMsg::BeginSearch { cards, .. }) => Some(cards),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(searched.len(), 1);
+    assert_eq!(searched[0].len(), 1);
+    assert_eq!(searched[0][0].name, "BloodFlask");
+}
+
+#[test]
+fn stolen_cards_return_to_their_owner() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.send(away, ClientMsg::CreateCard("Daemon".to_owned()));
+    let card = table.view(away).hand[0].id;
+    table.script([
+        (
+            away,
+            ClientMsg::Move {
+                from: PlaceFrom::Hand(card),
+                to: PlaceTo::Space(RelSide::Same, Space(0), false),
+            },
+        ),
+        (
+            home,
+            ClientMsg::Move {
+                from: PlaceFrom::Space(RelSide::Other, Space(0)),
+                to: PlaceTo::Hand,
+            },
+        ),
+    ]);
+    assert_eq!(table.view(home).hand[0].id, card);
+    assert_eq!(table.game.origins[&card].owner, Side::Away);
+
+    table.send(home, ClientMsg::ReturnToOwner(PlaceFrom::Hand(card)));
+
+    assert!(table.view(home).hand.is_empty());
+    assert_eq!(table.view(away).hand[0].id, card);
+}
+
+#[test]
+fn gathering_rebuilds_the_original_deck_lists() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, deck(&["Old"])),
+        (home, deck(&["A", "B", "C"])),
+        (away, deck(&["D", "E"])),
+        (home, ClientMsg::Draw(RelSide::Same, DeckType::Main)),
+        (home, ClientMsg::Draw(RelSide::Other, DeckType::Main)),
+        (away, ClientMsg::CreateCard("Token".to_owned())),
+    ]);
+    let stolen = table.view(home).hand[1].id;
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(stolen),
+            to: PlaceTo::Discard(RelSide::Same),
+        },
+    );
+
+    table.game.gather_decks();
+
+    let names = |side| -> Vec<&str> {
+        table
+            .game
+            .state
+            .get_state(side)
+            .main_deck
+            .iter()
+            .map(|id| table.game.cards[id].as_str())
+            .collect()
+    };
+    assert_eq!(names(Side::Home), ["A", "B", "C"]);
+    assert_eq!(names(Side::Away), ["D", "E"]);
+    assert!(table.game.state.home_state.discard.is_empty());
+    assert!(table.game.state.away_state.hand.is_empty());
+}
+
+#[test]
+fn health_is_tracked_per_player() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, ClientMsg::AddHealth(RelSide::Same, -3)),
+        (home, ClientMsg::AddHealth(RelSide::Other, 5)),
+    ]);
+
+    assert_eq!(table.view(home).local_state.health, 17);
+    assert_eq!(table.view(home).distant_state.health, 25);
+    assert_eq!(table.view(away).local_state.health, 25);
+    assert_eq!(table.view(away).distant_state.health, 17);
+    assert!(table.view(home).result.is_none());
+}
+
+#[test]
+fn running_out_of_health_ends_the_game() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.send(home, ClientMsg::AddHealth(RelSide::Other, -100));
+
+    assert_eq!(table.view(away).local_state.health, 0);
+    let result = |player| table.view(player).result.unwrap();
+    assert_eq!(result(home).winner, Some(RelSide::Same));
+    assert_eq!(result(away).winner, Some(RelSide::Other));
+    assert_eq!(result(home).reason, GameOverReason::OutOfHealth);
+    assert_eq!(table.game.state.result.unwrap().winner, Some(Side::Home));
+}
+
+#[test]
+fn conceding_ends_the_game() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.send(home, ClientMsg::StartGame);
+    let out = table.send(away, ClientMsg::Concede);
+
+    assert!(out.iter().any(|x| matches!(
+        x.message,
+        Ok(ServerMsg::GameOver {
+            winner: Some(RelSide::Same),
+            reason: GameOverReason::Conceded,
+        })
+    )));
+    assert_eq!(table.view(home).result.unwrap().winner, Some(RelSide::Same));
+    assert_eq!(
+        table.view(away).result.unwrap().winner,
+        Some(RelSide::Other)
+    );
+
+    table.send(home, ClientMsg::Concede);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::GameIsOver]
+    ));
+}
+
+#[test]
+fn games_have_to_start_before_they_can_end() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, ClientMsg::Concede),
+        (home, ClientMsg::OfferDraw),
+        (away, ClientMsg::AcceptDraw),
+    ]);
+    assert_eq!(table.take_errors(home).len(), 2);
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::GameNotStarted]
+    ));
+    assert!(table.view(home).result.is_none());
+    assert_eq!(table.view(home).score.games_played(), 0);
+}
+
+#[test]
+fn draws_need_to_be_offered_and_accepted() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, ClientMsg::StartGame),
+        (home, ClientMsg::AcceptDraw),
+        (home, ClientMsg::OfferDraw),
+    ]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoDrawOffered]
+    ));
+    assert_eq!(table.view(away).draw_offer, Some(RelSide::Other));
+
+    table.send(home, ClientMsg::AcceptDraw);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoDrawOffered]
+    ));
+
+    table.send(away, ClientMsg::AcceptDraw);
+    let result = table.view(home).result.unwrap();
+    assert_eq!(result.winner, None);
+    assert_eq!(result.reason, GameOverReason::AgreedDraw);
+    assert_eq!(table.view(home).draw_offer, None);
+}
+
+#[test]
+fn rematches_keep_the_decks_and_alternate_who_starts() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, no_opening_hand()),
+        (away, ClientMsg::AcceptStartRules),
+        (home, deck(&["A", "B", "C"])),
+        (away, deck(&["D", "E"])),
+        (home, ClientMsg::StartGame),
+        (home, ClientMsg::Draw(RelSide::Same, DeckType::Main)),
+        (away, ClientMsg::AddHealth(RelSide::Same, -5)),
+        (home, ClientMsg::Rematch),
+    ]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::GameNotOver]
+    ));
+
+    let first = table.game.state.turn.whose;
+    table.script([(away, ClientMsg::Concede), (home, ClientMsg::Rematch)]);
+
+    let view = table.view(home);
+    assert!(view.result.is_none());
+    assert!(view.hand.is_empty());
+    assert_eq!(view.local_state.health, STARTING_HEALTH);
+    assert_eq!(view.distant_state.health, STARTING_HEALTH);
+    assert_eq!(table.game.state.home_state.main_deck.len(), 3);
+    assert_eq!(table.game.state.away_state.main_deck.len(), 2);
+    assert_eq!(table.game.state.turn.whose, first.opposite());
+    assert_eq!(table.game.get_side(away), Some(Side::Away));
+
+    table.script([(home, ClientMsg::Concede), (away, ClientMsg::Rematch)]);
+    assert_eq!(table.game.state.turn.whose, first);
+}
+
+#[test]
+fn matches_are_best_of_three_by_default() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.send(home, ClientMsg::StartGame);
+    let first = table.game.state.turn.whose;
+    table.script([(away, ClientMsg::Concede), (home, ClientMsg::Rematch)]);
+    let score = table.view(away).score;
+    assert_eq!(score.best_of, 3);
+    assert_eq!((score.local_wins, score.distant_wins), (0, 1));
+    assert!(!score.is_over());
+    assert_eq!(table.game.state.turn.whose, first.opposite());
+
+    table.script([(home, ClientMsg::OfferDraw), (away, ClientMsg::AcceptDraw)]);
+    table.script([(home, ClientMsg::Rematch), (away, ClientMsg::Concede)]);
+    let score = table.view(home).score;
+    assert_eq!(
+        (score.local_wins, score.distant_wins, score.draws),
+        (2, 0, 1)
+    );
+    assert_eq!(score.winner, Some(RelSide::Same));
+    assert_eq!(table.view(away).score.winner, Some(RelSide::Other));
+
+    table.send(home, ClientMsg::Rematch);
+    let score = table.view(home).score;
+    assert_eq!(score.games_played(), 0);
+    assert_eq!(score.best_of, 3);
+}
+
+#[test]
+fn match_length_is_fixed_once_a_game_is_recorded() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, ClientMsg::SetMatchLength(1)),
+        (home, ClientMsg::StartGame),
+        (away, ClientMsg::Concede),
+        (home, ClientMsg::Rematch),
+    ]);
+    assert_eq!(table.view(home).score.games_played(), 0);
+
+    table.script([
+        (home, ClientMsg::SetMatchLength(0)),
+        (home, ClientMsg::SetMatchLength(3)),
+        (away, ClientMsg::Concede),
+        (home, ClientMsg::SetMatchLength(1)),
+        (home, ClientMsg::Rematch),
+        (away, ClientMsg::SetMatchLength(5)),
+    ]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::MatchTooShort, ServerErr::MatchUnderway]
+    ));
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::MatchUnderway]
+    ));
+    let score = table.view(home).score;
+    assert_eq!(score.best_of, 3);
+    assert_eq!(score.games_played(), 1);
+    assert!(!score.is_over());
+}
+
+#[test]
+fn sideboarding_between_games_changes_the_next_deck() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, no_opening_hand()),
+        (away, ClientMsg::AcceptStartRules),
+        (home, deck(&["A", "B", "C"])),
+        (home, ClientMsg::StartGame),
+        (away, ClientMsg::Concede),
+        (home, deck(&["A", "B", "D", "E"])),
+        (home, ClientMsg::Rematch),
+    ]);
+
+    let mut names: Vec<&str> = table
+        .game
+        .state
+        .home_state
+        .main_deck
+        .iter()
+        .map(|id| table.game.cards[id].as_str())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["A", "B", "D", "E"]);
+    assert_eq!(table.view(home).score.local_wins, 1);
+}
+
+fn opening_rules(main_cards: usize, mulligan: MulliganRule) -> ClientMsg {
+    ClientMsg::SetStartRules(StartRules {
+        main_cards,
+        blood_cards: 1,
+        mulligan,
+    })
+}
+
+#[test]
+fn starting_a_game_deals_opening_hands() {
+    let cards: Vec<String> = (0..10).map(|x| x.to_string()).collect();
+    let cards: Vec<&str> = cards.iter().map(String::as_str).collect();
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, opening_rules(4, MulliganRule::Free)),
+        (away, ClientMsg::AcceptStartRules),
+        (home, deck(&cards)),
+        (away, deck(&cards)),
+        (
+            away,
+            ClientMsg::SetDeck(DeckType::Blood, ["Flask".to_owned()].into()),
+        ),
+        (home, ClientMsg::StartGame),
+    ]);
+
+    assert_eq!(table.view(home).hand.len(), 4);
+    assert_eq!(table.view(away).hand.len(), 5);
+    assert!(table.view(away).hand.iter().any(|x| x.name == "Flask"));
+    assert_eq!(table.game.state.home_state.main_deck.len(), 6);
+    assert!(table.view(home).mulligan.is_some());
+    assert_eq!(table.view(home).start_rules.main_cards, 4);
+}
+
+#[test]
+fn the_starting_player_is_picked_at_random() {
+    let starter = |seed| {
+        let mut table = Table::with_seed(seed);
+        let home = table.sit();
+        table.send(home, ClientMsg::StartGame);
+        table.game.state.turn.whose
+    };
+
+    let starters: Vec<Side> = (0..16).map(starter).collect();
+    assert!(starters.contains(&Side::Home));
+    assert!(starters.contains(&Side::Away));
+    assert_eq!(starter(3), starter(3));
+}
+
+#[test]
+fn mulligans_draw_one_fewer_card_until_everyone_keeps() {
+    let cards: Vec<String> = (0..10).map(|x| x.to_string()).collect();
+    let cards: Vec<&str> = cards.iter().map(String::as_str).collect();
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, opening_rules(5, MulliganRule::OneFewer)),
+        (away, ClientMsg::AcceptStartRules),
+        (home, deck(&cards)),
+        (home, ClientMsg::StartGame),
+        (home, ClientMsg::Mulligan),
+        (home, ClientMsg::Mulligan),
+    ]);
+    assert_eq!(table.view(home).hand.len(), 3);
+    assert_eq!(table.game.state.home_state.main_deck.len(), 7);
+    assert_eq!(table.view(away).mulligan.unwrap().distant.mulligans, 2);
+
+    table.script([
+        (home, ClientMsg::KeepHand),
+        (home, ClientMsg::Mulligan),
+        (home, ClientMsg::TurnSet(TurnStep::Main)),
+    ]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NotMulliganing, ServerErr::StillMulliganing]
+    ));
+
+    table.send(away, ClientMsg::KeepHand);
+    assert!(table.view(home).mulligan.is_none());
+    assert_eq!(table.view(home).turn.step, TurnStep::Start);
+}
+
+#[test]
+fn start_rules_change_once_the_other_player_accepts() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (home, opening_rules(3, MulliganRule::Disabled)),
+        (home, ClientMsg::AcceptStartRules),
+    ]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoStartRulesProposed]
+    ));
+    assert_eq!(table.view(home).start_rules, StartRules::default());
+    let (by, rules) = table.view(away).proposed_start_rules.unwrap();
+    assert_eq!(by, RelSide::Other);
+    assert_eq!(rules.main_cards, 3);
+
+    table.send(away, ClientMsg::AcceptStartRules);
+    assert_eq!(table.view(home).start_rules.main_cards, 3);
+    assert!(table.view(home).proposed_start_rules.is_none());
+}
+
+#[test]
+fn games_cant_be_dealt_again_until_they_end() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([(home, ClientMsg::StartGame), (away, ClientMsg::StartGame)]);
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::GameInProgress]
+    ));
+
+    table.script([(away, ClientMsg::Concede), (away, ClientMsg::StartGame)]);
+    assert!(table.take_errors(away).is_empty());
+    assert!(table.view(home).result.is_none());
+}
+
+#[test]
+fn the_first_turn_runs_its_start_step_actions_once_hands_are_kept() {
+    let mut table = Table::with_seed(1);
+    let home = table.sit();
+    let away = table.sit();
+    let mut hooks = StepHook::defaults();
+    hooks[1].enabled = true;
+
+    table.script([
+        (home, ClientMsg::SetHooks(hooks.clone())),
+        (away, ClientMsg::SetHooks(hooks)),
+        (home, opening_rules(0, MulliganRule::Free)),
+        (away, ClientMsg::AcceptStartRules),
+        (home, ClientMsg::StartGame),
+        (home, ClientMsg::KeepHand),
+    ]);
+    let starter = table.game.state.turn.whose;
+    assert_eq!(table.game.state.get_state(starter).blood, 0);
+
+    table.send(away, ClientMsg::KeepHand);
+    assert_eq!(table.game.state.get_state(starter).blood, 1);
+    assert_eq!(table.game.state.get_state(starter.opposite()).blood, 0);
+}
+
+fn random_events(out: &[DestinedServerMsg], player: PlayerId) -> Vec<LocalHistoryEntry> {
+    out.iter()
+        .filter(|x| matches!(x.author, Destination::Player(to) if to == player))
+        .filter_map(|x| match &x.message {
+            Ok(ServerMsg::History(entry)) => Some(entry.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn rolls_are_seen_by_everyone_and_kept_in_the_history() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    let spectator = table.connect();
+
+    let out = table.send(home, ClientMsg::Roll { sides: 6, count: 3 });
+
+    for player in [home, away, spectator] {
+        let events = random_events(&out, player);
+        let [entry] = events.as_slice() else {
+            panic!("{player:?} got {events:?}");
+        };
+        let LocalHistoryEvent::Roll { sides, results } = &entry.event else {
+            panic!("not a roll");
+        };
+        assert_eq!(*sides, 6);
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|x| (1..=6).contains(x)));
+    }
+    assert_eq!(random_events(&out, away)[0].by, RelSide::Other);
+
+    table.script([
+        (away, ClientMsg::StartGame),
+        (away, ClientMsg::FlipCoin),
+        (away, ClientMsg::Concede),
+        (home, ClientMsg::Rematch),
+    ]);
+    let history = &table.view(away).history;
+    assert_eq!(history.len(), 2);
+    assert!(matches!(
+        history[1].event,
+        LocalHistoryEvent::CoinFlip { .. }
+    ));
+    assert_eq!(history[1].by, RelSide::Same);
+}
+
+#[test]
+fn rolling_nothing_is_an_error() {
+    let mut table = Table::new();
+    let home = table.sit();
+
+    table.script([
+        (home, ClientMsg::Roll { sides: 0, count: 1 }),
+        (home, ClientMsg::Roll { sides: 6, count: 0 }),
+    ]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::BadRoll, ServerErr::BadRoll]
+    ));
+    assert!(table.game.history.is_empty());
+}
+
+#[test]
+fn random_cards_from_a_hand_stay_hidden_from_the_opponent() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.send(home, ClientMsg::CreateCard("Daemon".to_owned()));
+    let out = table.send(away, ClientMsg::PickRandom(RelSide::Other, Zone::Hand));
+
+    let card = |player| match &random_events(&out, player)[0].event {
+        LocalHistoryEvent::RandomCard {
+            card, owner, zone, ..
+        } => (card.clone(), *owner, *zone),
+        _ => panic!("not a random card"),
+    };
+    assert!(
+        matches!(card(home), (Hidden::Unhidden(x), RelSide::Same, Zone::Hand) if x.name == "Daemon")
+    );
+    assert!(matches!(
+        card(away),
+        (Hidden::Hidden, RelSide::Other, Zone::Hand)
+    ));
+
+    table.send(away, ClientMsg::PickRandom(RelSide::Same, Zone::Discard));
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::NoCardsIn(RelSide::Same, Zone::Discard)]
+    ));
+}
+
+fn looked_at(out: &[DestinedServerMsg]) -> Vec<(CardId, String)> {
+    out.iter()
+        .find_map(|x| match &x.message {
+            Ok(ServerMsg::LookAtTop { cards, .. }) => {
+                Some(cards.iter().map(|x| (x.id, x.name.clone())).collect())
+            }
+            _ => None,
+        })
+        .expect("nothing to look at")
+}
+
+#[test]
+fn looking_at_the_top_shows_only_the_top_cards() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(away, deck(&["E", "D", "C", "B", "A"]));
+
+    let look = ClientMsg::LookAtTop {
+        deck: DeckType::Main,
+        n: 3,
+        side: RelSide::Other,
+    };
+    table.script([(home, look.clone()), (away, ClientMsg::AnswerSearch(false))]);
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::SearchDenied]
+    ));
+    assert!(table.game.state.home_state.looking_at.is_none());
+
+    let out = table.send(home, look);
+    assert!(
+        !out.iter()
+            .any(|x| matches!(x.message, Ok(ServerMsg::LookAtTop { .. })))
+    );
+    let request = table.view(away).search_request.unwrap();
+    assert_eq!((request.by, request.top), (RelSide::Other, Some(3)));
+
+    let out = table.send(away, ClientMsg::AnswerSearch(true));
+    let names: Vec<String> = looked_at(&out).into_iter().map(|x| x.1).collect();
+    assert_eq!(names, ["A", "B", "C"]);
+    for x in &out {
+        match &x.message {
+            Ok(ServerMsg::LookAtTop { .. }) => {
+                assert!(matches!(x.author, Destination::Player(to) if to == home));
+            }
+            Ok(ServerMsg::LookedAtTop { by, side, n, .. }) => {
+                assert!(matches!(x.author, Destination::Player(to) if to == away));
+                assert_eq!((*by, *side, *n), (RelSide::Other, RelSide::Same, 3));
+            }
+            _ => {}
+        }
+    }
+    assert!(
+        out.iter()
+            .any(|x| matches!(x.message, Ok(ServerMsg::LookedAtTop { .. })))
+    );
+}
+
+#[test]
+fn looking_at_the_whole_deck_is_a_search() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, deck(&["C", "B", "A"]));
+    table.send(away, deck(&["B", "A"]));
+
+    for (n, side) in [(3, RelSide::Same), (5, RelSide::Same), (2, RelSide::Other)] {
+        table.send(
+            home,
+            ClientMsg::LookAtTop {
+                deck: DeckType::Main,
+                n,
+                side,
+            },
+        );
+    }
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [
+            ServerErr::LookAtWholeDeck,
+            ServerErr::LookAtWholeDeck,
+            ServerErr::LookAtWholeDeck
+        ]
+    ));
+    assert!(table.game.state.home_state.looking_at.is_none());
+    assert!(table.game.state.search_request.is_none());
+
+    let out = table.send(
+        home,
+        ClientMsg::LookAtTop {
+            deck: DeckType::Main,
+            n: 2,
+            side: RelSide::Same,
+        },
+    );
+    assert_eq!(looked_at(&out).len(), 2);
+}
+
+#[test]
+fn arranging_the_top_moves_each_card_and_only_tells_the_counts() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, deck(&["E", "D", "C", "B", "A"]));
+
+    let out = table.send(
+        home,
+        ClientMsg::LookAtTop {
+            deck: DeckType::Main,
+            n: 4,
+            side: RelSide::Same,
+        },
+    );
+    let ids: Vec<CardId> = looked_at(&out).into_iter().map(|x| x.0).collect();
+    let [a, b, c, d] = ids[..] else {
+        panic!("looked at {ids:?}");
+    };
+
+    let out = table.send(
+        home,
+        ClientMsg::ArrangeTop(TopArrangement {
+            top: vec![c, a],
+            bottom: vec![b],
+            hand: vec![d],
+            discard: vec![],
+        }),
+    );
+
+    let names = |ids: &std::collections::VecDeque<CardId>| -> Vec<String> {
+        ids.iter()
+            .rev()
+            .map(|id| table.game.cards[id].clone())
+            .collect()
+    };
+    assert_eq!(
+        names(&table.game.state.home_state.main_deck),
+        ["C", "A", "E", "B"]
+    );
+    assert_eq!(table.view(home).hand[0].name, "D");
+    let told = out
+        .iter()
+        .find_map(|x| match (&x.author, &x.message) {
+            (Destination::Player(to), Ok(ServerMsg::TopArranged { counts, by, .. }))
+                if *to == away =>
+            {
+                Some((*counts, *by))
+            }
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        told,
+        (
+            ArrangedCounts {
+                top: 2,
+                bottom: 1,
+                hand: 1,
+                discard: 0
+            },
+            RelSide::Other
+        )
+    );
+
+    table.send(home, ClientMsg::ArrangeTop(TopArrangement::default()));
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NotLooking]
+    ));
+}
+
+#[test]
+fn arrangements_have_to_use_every_card_once() {
+    let mut table = Table::new();
+    let home = table.sit();
+    table.send(home, deck(&["C", "B", "A"]));
+    let out = table.send(
+        home,
+        ClientMsg::LookAtTop {
+            deck: DeckType::Main,
+            n: 2,
+            side: RelSide::Same,
+        },
+    );
+    let ids: Vec<CardId> = looked_at(&out).into_iter().map(|x| x.0).collect();
+    table.take_inbox(home);
+
+    for top in [
+        vec![ids[0]],
+        vec![ids[0], ids[0]],
+        vec![ids[0], ids[1], CardId(99)],
+    ] {
+        table.send(
+            home,
+            ClientMsg::ArrangeTop(TopArrangement {
+                top,
+                ..TopArrangement::default()
+            }),
+        );
+    }
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [
+            ServerErr::BadArrangement,
+            ServerErr::BadArrangement,
+            ServerErr::BadArrangement
+        ]
+    ));
+    assert_eq!(table.game.state.home_state.main_deck.len(), 3);
+}
+
+#[test]
+fn arranging_after_the_deck_changed_is_refused() {
+    let cards: Vec<String> = (0..10).map(|x| x.to_string()).collect();
+    let cards: Vec<&str> = cards.iter().map(String::as_str).collect();
+    let mut table = Table::with_seed(3);
+    let home = table.sit();
+    table.send(home, deck(&cards));
+    let out = table.send(
+        home,
+        ClientMsg::LookAtTop {
+            deck: DeckType::Main,
+            n: 2,
+            side: RelSide::Same,
+        },
+    );
+    let ids: Vec<CardId> = looked_at(&out).into_iter().map(|x| x.0).collect();
+
+    table.send(home, ClientMsg::Shuffle(DeckType::Main));
+    let deck = &table.game.state.home_state.main_deck;
+    assert!(!deck.iter().rev().take(2).eq(&ids));
+
+    table.send(
+        home,
+        ClientMsg::ArrangeTop(TopArrangement {
+            hand: ids,
+            ..TopArrangement::default()
+        }),
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::DeckChangedSinceLook]
+    ));
+    assert!(table.view(home).hand.is_empty());
+    assert_eq!(table.game.state.home_state.main_deck.len(), 10);
+    assert!(table.game.state.home_state.looking_at.is_none());
+}
+
+#[test]
+fn drawing_and_milling_several_cards() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(away, deck(&["E", "D", "C", "B", "A"]));
+
+    table.script([
+        (home, ClientMsg::DrawMany(RelSide::Other, DeckType::Main, 2)),
+        (away, ClientMsg::Mill(RelSide::Same, DeckType::Main, 2)),
+        (away, ClientMsg::DrawMany(RelSide::Same, DeckType::Main, 10)),
+    ]);
+
+    let names =
+        |cards: &[NamedCardId]| -> Vec<String> { cards.iter().map(|x| x.name.clone()).collect() };
+    assert_eq!(names(&table.view(home).hand), ["A", "B"]);
+    assert_eq!(names(&table.view(home).distant_state.discard), ["C", "D"]);
+    assert_eq!(names(&table.view(away).hand), ["E"]);
+    assert!(matches!(
+        table.view(home).distant_state.main_deck_top,
+        LocalDeckTop::Empty
+    ));
+}
+
+#[test]
+fn revealed_tops_stay_revealed_until_the_top_changes() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, deck(&["C", "B", "A"]));
+
+    let top = |table: &Table| table.view(away).distant_state.main_deck_top.clone();
+    assert!(matches!(top(&table), LocalDeckTop::Card));
+
+    table.send(away, ClientMsg::RevealTop(RelSide::Other, DeckType::Main));
+    assert!(matches!(top(&table), LocalDeckTop::Revealed(x) if x == "A"));
+
+    table.send(home, ClientMsg::Draw(RelSide::Same, DeckType::Main));
+    assert!(matches!(top(&table), LocalDeckTop::Card));
+
+    table.send(
+        home,
+        ClientMsg::SetPlayWithTopRevealed(DeckType::Main, true),
+    );
+    assert!(matches!(top(&table), LocalDeckTop::Revealed(x) if x == "B"));
+    table.send(home, ClientMsg::Draw(RelSide::Same, DeckType::Main));
+    assert!(matches!(top(&table), LocalDeckTop::Revealed(x) if x == "C"));
+    assert!(table.view(home).local_state.main_always_revealed);
+}
+
+#[test]
+fn revealed_tops_are_forgotten_once_they_leave_the_top() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, deck(&["C", "B", "A"]));
+    let top = |table: &Table| table.view(away).distant_state.main_deck_top.clone();
+
+    table.script([
+        (away, ClientMsg::RevealTop(RelSide::Other, DeckType::Main)),
+        (home, ClientMsg::Draw(RelSide::Same, DeckType::Main)),
+    ]);
+    let card = table.view(home).hand[0].id;
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(card),
+            to: PlaceTo::Deck(DeckTo::Top, RelSide::Same, DeckType::Main),
+        },
+    );
+    assert!(matches!(top(&table), LocalDeckTop::Card));
+
+    // Shuffling forgets it too, even if the same card ends up on top
+    table.script([
+        (away, ClientMsg::RevealTop(RelSide::Other, DeckType::Main)),
+        (home, ClientMsg::Shuffle(DeckType::Main)),
+    ]);
+    assert!(matches!(top(&table), LocalDeckTop::Card));
+}
+
+fn searches(out: &[DestinedServerMsg], player: PlayerId) -> Vec<(RelSide, DeckType, Vec<String>)> {
+    out.iter()
+        .filter(|x| matches!(x.author, Destination::Player(to) if to == player))
+        .filter_map(|x| match &x.message {
+            Ok(ServerMsg::BeginSearch { side, deck, cards }) => {
+                Some((*side, *deck, cards.iter().map(|x| x.name.clone()).collect()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn searching_the_opponents_deck_needs_their_consent() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(
+        away,
+        ClientMsg::SetDeck(DeckType::Blood, ["Flask".to_owned()].into()),
+    );
+
+    let out = table.send(
+        home,
+        ClientMsg::RequestSearch(RelSide::Other, DeckType::Blood),
+    );
+    assert!(searches(&out, home).is_empty());
+    let request = table.view(away).search_request.unwrap();
+    assert_eq!(
+        (request.by, request.deck),
+        (RelSide::Other, DeckType::Blood)
+    );
+
+    table.send(home, ClientMsg::AnswerSearch(true));
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoSearchRequest]
+    ));
+
+    let out = table.send(away, ClientMsg::AnswerSearch(true));
+    assert_eq!(
+        searches(&out, home),
+        [(RelSide::Other, DeckType::Blood, vec!["Flask".to_owned()])]
+    );
+    assert!(searches(&out, away).is_empty());
+    assert!(table.view(home).search_request.is_none());
+}
+
+#[test]
+fn denied_searches_tell_the_searcher() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (
+            home,
+            ClientMsg::RequestSearch(RelSide::Other, DeckType::Main),
+        ),
+        (away, ClientMsg::AnswerSearch(false)),
+    ]);
+
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::SearchDenied]
+    ));
+    assert!(table.game.state.home_state.searching.is_none());
+}
+
+#[test]
+fn finishing_a_search_tells_everyone_whether_it_was_shuffled() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, deck(&["A"]));
+
+    table.send(
+        home,
+        ClientMsg::RequestSearch(RelSide::Same, DeckType::Main),
+    );
+    let out = table.send(home, ClientMsg::FinishSearch { shuffle: true });
+
+    let told = out.iter().find_map(|x| match (&x.author, &x.message) {
+        (
+            Destination::Player(to),
+            Ok(ServerMsg::Searched {
+                by,
+                side,
+                deck,
+                shuffled,
+            }),
+        ) if *to == away => Some((*by, *side, *deck, *shuffled)),
+        _ => None,
+    });
+    assert_eq!(
+        told,
+        Some((RelSide::Other, RelSide::Other, DeckType::Main, true))
+    );
+
+    table.send(home, ClientMsg::FinishSearch { shuffle: false });
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NotSearching]
+    ));
+}
+
+fn reveals(out: &[DestinedServerMsg], player: PlayerId) -> Vec<(RelSide, Vec<String>)> {
+    out.iter()
+        .filter(|x| matches!(x.author, Destination::Player(to) if to == player))
+        .filter_map(|x| match &x.message {
+            Ok(ServerMsg::Revealed { by, cards }) => {
+                Some((*by, cards.iter().map(|x| x.name.clone()).collect()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn revealed_hand_cards_are_known_until_they_leave_the_hand() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    let spectator = table.connect();
+    table.script([
+        (home, deck(&["C", "B", "A"])),
+        (home, ClientMsg::DrawMany(RelSide::Same, DeckType::Main, 3)),
+    ]);
+    let hand = table.view(home).hand.clone();
+    assert_eq!(table.view(away).distant_state.hand_count, 3);
+
+    let out = table.send(
+        home,
+        ClientMsg::Reveal {
+            cards: vec![PlaceFrom::Hand(hand[0].id), PlaceFrom::Hand(hand[1].id)],
+            to: RevealTarget::Opponent,
+        },
+    );
+    let shown = vec!["A".to_owned(), "B".to_owned()];
+    assert_eq!(reveals(&out, away), [(RelSide::Other, shown.clone())]);
+    assert!(reveals(&out, home).is_empty());
+    assert!(reveals(&out, spectator).is_empty());
+
+    let known = |table: &Table, player| -> Vec<String> {
+        let view = table.view(player);
+        let state = if player == home {
+            &view.local_state
+        } else {
+            &view.distant_state
+        };
+        state.revealed_hand.iter().map(|x| x.name.clone()).collect()
+    };
+    assert_eq!(known(&table, away), shown);
+    assert_eq!(known(&table, home), shown);
+    assert!(known(&table, spectator).is_empty());
+
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(hand[0].id),
+            to: PlaceTo::Discard(RelSide::Same),
+        },
+    );
+    assert_eq!(known(&table, away), ["B"]);
+    assert_eq!(table.view(away).distant_state.hand_count, 2);
+}
+
+#[test]
+fn revealing_to_everyone_includes_spectators() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    let spectator = table.connect();
+    table.send(home, ClientMsg::CreateCard("Daemon".to_owned()));
+    let card = table.view(home).hand[0].id;
+
+    let out = table.send(
+        home,
+        ClientMsg::Reveal {
+            cards: vec![PlaceFrom::Hand(card)],
+            to: RevealTarget::Everyone,
+        },
+    );
+    let shown = vec!["Daemon".to_owned()];
+    assert_eq!(reveals(&out, away), [(RelSide::Other, shown.clone())]);
+    // Spectators watch from the home side.
+    assert_eq!(reveals(&out, spectator), [(RelSide::Same, shown)]);
+    assert_eq!(table.view(spectator).local_state.revealed_hand.len(), 1);
+
+    table.send(
+        home,
+        ClientMsg::Reveal {
+            cards: vec![PlaceFrom::Hand(CardId(999))],
+            to: RevealTarget::Everyone,
+        },
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoCardIn(PlaceFrom::Hand(CardId(999)))]
+    ));
+}
+
+#[test]
+fn everyone_sees_how_many_cards_are_in_hands_and_decks() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    let spectator = table.connect();
+    table.script([
+        (home, deck(&["C", "B", "A"])),
+        (
+            home,
+            ClientMsg::SetDeck(DeckType::Blood, ["Flask".to_owned()].into()),
+        ),
+        (home, ClientMsg::Draw(RelSide::Same, DeckType::Main)),
+        (home, ClientMsg::CreateCard("Daemon".to_owned())),
+    ]);
+    let card = table.view(home).hand[0].id;
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(card),
+            to: PlaceTo::Aside,
+        },
+    );
+
+    for (player, state) in [
+        (away, &table.view(away).distant_state),
+        (spectator, &table.view(spectator).local_state),
+    ] {
+        assert_eq!(state.hand_count, 1, "{player:?}");
+        assert_eq!(state.main_deck_size, 2, "{player:?}");
+        assert_eq!(state.blood_deck_size, 1, "{player:?}");
+        assert_eq!(table.view(player).aside_count, 1);
+    }
+}
+
+#[test]
+fn the_most_recent_discard_is_last() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.script([
+        (home, deck(&["B", "A"])),
+        (home, ClientMsg::DrawMany(RelSide::Same, DeckType::Main, 2)),
+    ]);
+    let hand = table.view(home).hand.clone();
+    for card in hand.iter().rev() {
+        table.send(
+            home,
+            ClientMsg::Move {
+                from: PlaceFrom::Hand(card.id),
+                to: PlaceTo::Discard(RelSide::Same),
+            },
+        );
+    }
+
+    let discard = &table.view(away).distant_state.discard;
+    let names: Vec<_> = discard.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, ["B", "A"]);
+}
+
+/// Puts a new creature with the given counters in the player's row.
+fn creature(table: &mut Table, player: PlayerId, space: Space, pow: isize, def: isize, hp: isize) {
+    table.send(player, ClientMsg::CreateCard("Daemon".to_owned()));
+    let card = table.view(player).hand[0].id;
+    table.send(
+        player,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(card),
+            to: PlaceTo::Space(RelSide::Same, space, false),
+        },
+    );
+    let from = CounterTarget::Card(PlaceFrom::Space(RelSide::Same, space));
+    for (counter, amount) in [(POWER, pow), (DEFENSE, def), (HEALTH, hp)] {
+        table.send(
+            player,
+            ClientMsg::AddCounter(from, counter.to_owned(), amount),
+        );
+    }
+}
+
+#[test]
+fn combat_deals_damage_once_both_players_confirm() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    creature(&mut table, home, Space(0), 3, 0, 2);
+    creature(&mut table, home, Space(1), 2, 0, 1);
+    creature(&mut table, away, Space(0), 1, 1, 2);
+    table.send(home, ClientMsg::TurnSet(TurnStep::Combat));
+
+    table.script([
+        (
+            home,
+            ClientMsg::DeclareAttack(vec![
+                Attack {
+                    from: Space(0),
+                    target: AttackTarget::Player,
+                },
+                Attack {
+                    from: Space(1),
+                    target: AttackTarget::Player,
+                },
+            ]),
+        ),
+        (
+            away,
+            ClientMsg::DeclareBlocks(vec![Block {
+                blocker: Space(0),
+                attacker: Space(0),
+            }]),
+        ),
+        (home, ClientMsg::ConfirmCombat),
+    ]);
+    let combat = table.view(away).combat.clone().unwrap();
+    assert_eq!(combat.attacker, RelSide::Other);
+    assert!(combat.distant_confirmed && !combat.local_confirmed);
+    assert_eq!(
+        combat.hits,
+        [
+            LocalHit {
+                target: LocalHitTarget::Card(RelSide::Same, Space(0)),
+                damage: 2,
+            },
+            LocalHit {
+                target: LocalHitTarget::Card(RelSide::Other, Space(0)),
+                damage: 1,
+            },
+            LocalHit {
+                target: LocalHitTarget::Player(RelSide::Same),
+                damage: 2,
+            },
+        ]
+    );
+    assert_eq!(table.view(away).local_state.health, STARTING_HEALTH);
+
+    table.send(away, ClientMsg::ConfirmCombat);
+    let view = table.view(away);
+    assert!(view.combat.is_none());
+    assert_eq!(view.local_state.health, STARTING_HEALTH - 2);
+    assert!(view.local_row[Space(0)].is_none());
+    assert_eq!(view.local_state.discard.len(), 1);
+    let survivor = view.distant_row[Space(0)].as_ref().unwrap();
+    assert_eq!(survivor.counters.get(HEALTH), Some(&1));
+}
+
+#[test]
+fn attacks_have_to_come_from_the_active_player_during_combat() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    creature(&mut table, home, Space(0), 1, 0, 1);
+    let attack = || {
+        ClientMsg::DeclareAttack(vec![Attack {
+            from: Space(0),
+            target: AttackTarget::Player,
+        }])
+    };
+
+    table.send(home, attack());
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NotCombatStep]
+    ));
+
+    table.send(home, ClientMsg::TurnSet(TurnStep::Combat));
+    table.send(away, attack());
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::NotYourTurn]
+    ));
+
+    table.send(
+        home,
+        ClientMsg::DeclareAttack(vec![Attack {
+            from: Space(1),
+            target: AttackTarget::Player,
+        }]),
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::BadAttack]
+    ));
+
+    table.send(home, attack());
+    assert!(table.view(home).combat.is_some());
+    table.send(home, ClientMsg::TurnSet(TurnStep::End));
+    assert!(table.view(home).combat.is_none());
+}
+
+#[test]
+fn step_actions_run_when_their_step_begins() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(away, deck(&["B", "A"]));
+    creature(&mut table, away, Space(0), 1, 0, 1);
+    let mut hooks = StepHook::defaults();
+    for hook in &mut hooks {
+        hook.enabled = true;
+    }
+    hooks.push(StepHook {
+        step: TurnStep::Combat,
+        action: StepAction::ClearCounter(POWER.to_owned()),
+        enabled: true,
+    });
+    table.send(away, ClientMsg::SetHooks(hooks));
+
+    // Nothing happens on home's turn
+    table.send(home, ClientMsg::TurnSet(TurnStep::Main));
+    assert!(table.view(away).hand.is_empty());
+
+    let out = table.send(home, ClientMsg::TurnSet(TurnStep::Switch));
+    let view = table.view(away);
+    assert_eq!(view.hand.len(), 1);
+    assert_eq!(view.local_state.blood, 1);
+    let logged: Vec<_> = random_events(&out, home)
+        .into_iter()
+        .map(|x| (x.by, x.event))
+        .collect();
+    assert!(matches!(
+        logged.as_slice(),
+        [
+            (
+                RelSide::Other,
+                LocalHistoryEvent::Automated {
+                    step: TurnStep::Start,
+                    action: StepAction::Draw(DeckType::Main),
+                },
+            ),
+            (
+                RelSide::Other,
+                LocalHistoryEvent::Automated {
+                    action: StepAction::GainBlood,
+                    ..
+                },
+            ),
+            (
+                RelSide::Other,
+                LocalHistoryEvent::Automated {
+                    action: StepAction::ClearStatus(Status::Exhausted),
+                    ..
+                },
+            ),
+            (
+                RelSide::Other,
+                LocalHistoryEvent::Automated {
+                    action: StepAction::ClearStatus(Status::SummoningSick),
+                    ..
+                },
+            ),
+        ]
+    ));
+
+    // Setting the same step again doesn't run anything twice
+    table.send(away, ClientMsg::TurnSet(TurnStep::Start));
+    assert_eq!(table.view(away).hand.len(), 1);
+
+    table.send(away, ClientMsg::TurnSet(TurnStep::Combat));
+    let card = table.view(away).local_row[Space(0)].clone().unwrap();
+    assert_eq!(card.counters.get(POWER), None);
+    assert_eq!(table.game.history.len(), 5);
+}
+
+#[test]
+fn strict_rooms_only_let_whoever_has_priority_act() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.script([
+        (home, ClientMsg::SetStrict(true)),
+        (home, deck(&["A"])),
+        (away, deck(&["B"])),
+    ]);
+    assert!(table.view(away).strict);
+
+    table.send(away, ClientMsg::Draw(RelSide::Same, DeckType::Main));
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::NoPriority]
+    ));
+
+    table.send(home, ClientMsg::PassPriority);
+    assert_eq!(table.view(away).priority, RelSide::Same);
+    // Priority lets them respond, but the turn is still home's
+    table.send(away, ClientMsg::TurnSet(TurnStep::Main));
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::NotYourTurn]
+    ));
+    table.send(home, ClientMsg::TurnSet(TurnStep::Main));
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoPriority]
+    ));
+    table.script([
+        (away, ClientMsg::Draw(RelSide::Same, DeckType::Main)),
+        (away, ClientMsg::PassPriority),
+        (home, ClientMsg::TurnSet(TurnStep::Switch)),
+    ]);
+    assert_eq!(table.view(away).hand.len(), 1);
+    assert_eq!(table.view(away).priority, RelSide::Same);
+    assert_eq!(table.view(away).turn.whose, RelSide::Same);
+}
+
+#[test]
+fn strictness_only_changes_between_games() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.script([
+        (home, ClientMsg::StartGame),
+        (away, ClientMsg::SetStrict(true)),
+    ]);
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::GameInProgress]
+    ));
+    assert!(!table.view(home).strict);
+
+    table.script([
+        (away, ClientMsg::Concede),
+        (away, ClientMsg::SetStrict(true)),
+    ]);
+    assert!(table.take_errors(away).is_empty());
+    assert!(table.view(home).strict);
+}
+
+#[test]
+fn relaxed_rooms_let_anyone_act() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.send(away, ClientMsg::TurnSet(TurnStep::Main));
+    assert!(table.take_errors(away).is_empty());
+    assert_eq!(table.view(home).turn.step, TurnStep::Main);
+}
+
+fn clock(timeout: TimeoutRule) -> ClientMsg {
+    ClientMsg::SetClock(Some(ClockRules {
+        bank: Duration::from_secs(60),
+        increment: Duration::from_secs(5),
+        timeout,
+    }))
+}
+
+/// Deals a game with a chess clock and no mulligans. Returns the players in turn order.
+fn clocked_game(timeout: TimeoutRule) -> (Table, PlayerId, PlayerId) {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.script([
+        (home, clock(timeout)),
+        (home, no_opening_hand()),
+        (away, ClientMsg::AcceptStartRules),
+        (home, ClientMsg::StartGame),
+    ]);
+    match table.game.state.turn.whose {
+        Side::Home => (table, home, away),
+        Side::Away => (table, away, home),
+    }
+}
+
+#[test]
+fn the_clock_runs_for_whoever_holds_priority_and_gives_increments() {
+    let (mut table, first, second) = clocked_game(TimeoutRule::Warn);
+
+    table.tick(Duration::from_secs(10));
+    let clocks = table.view(second).turn.clocks.unwrap();
+    assert_eq!(clocks.distant, Duration::from_secs(50));
+    assert_eq!(clocks.local, Duration::from_secs(60));
+    assert_eq!(clocks.running, Some(RelSide::Other));
+
+    table.send(first, ClientMsg::PassPriority);
+    table.tick(Duration::from_secs(20));
+    let clocks = table.view(first).turn.clocks.unwrap();
+    assert_eq!(clocks.local, Duration::from_secs(55));
+    assert_eq!(clocks.distant, Duration::from_secs(40));
+    assert_eq!(clocks.running, Some(RelSide::Other));
+}
+
+#[test]
+fn clocks_only_run_during_a_game() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, clock(TimeoutRule::Lose));
+
+    assert!(table.tick(Duration::from_secs(90)).is_empty());
+    let clocks = table.view(home).turn.clocks.unwrap();
+    assert_eq!(clocks.local, Duration::from_secs(60));
+    assert_eq!(clocks.running, None);
+
+    table.script([
+        (home, ClientMsg::StartGame),
+        (away, clock(TimeoutRule::Warn)),
+        (away, ClientMsg::SetClock(None)),
+    ]);
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::GameInProgress, ServerErr::GameInProgress]
+    ));
+    assert_eq!(
+        table.game.clock_rules.map(|x| x.timeout),
+        Some(TimeoutRule::Lose)
+    );
+}
+
+#[test]
+fn running_out_of_time_warns_everyone_once() {
+    let (mut table, _, second) = clocked_game(TimeoutRule::Warn);
+    table.take_inbox(second);
+
+    table.tick(Duration::from_secs(90));
+    table.tick(Duration::from_secs(1));
+    let warnings: Vec<_> = table
+        .take_inbox(second)
+        .into_iter()
+        .filter(|x| matches!(x, Ok(ServerMsg::OutOfTime(_))))
+        .collect();
+    assert!(matches!(
+        warnings.as_slice(),
+        [Ok(ServerMsg::OutOfTime(RelSide::Other))]
+    ));
+    assert!(table.view(second).result.is_none());
+
+    // Nothing left to tell anyone while the clock sits at zero
+    assert!(table.tick(Duration::from_secs(1)).is_empty());
+}
+
+#[test]
+fn running_out_of_time_can_pass_automatically_or_lose() {
+    let (mut table, first, second) = clocked_game(TimeoutRule::AutoPass);
+    table.send(first, ClientMsg::PassPriority);
+    // The second player was only responding, so priority goes back to the first
+    table.tick(Duration::from_secs(60));
+    assert_eq!(table.view(first).priority, RelSide::Same);
+    assert_eq!(table.view(first).turn.whose, RelSide::Same);
+    // The first player is the active player, so their turn ends
+    table.tick(Duration::from_secs(70));
+    assert_eq!(table.view(second).turn.whose, RelSide::Same);
+    assert_eq!(table.view(second).priority, RelSide::Same);
+
+    let (mut table, first, second) = clocked_game(TimeoutRule::Lose);
+    table.tick(Duration::from_secs(60));
+    let result = table.view(second).result.unwrap();
+    assert_eq!(result.winner, Some(RelSide::Same));
+    assert_eq!(result.reason, GameOverReason::OutOfTime);
+    // The clocks stop once the game is over
+    assert_eq!(table.view(first).turn.clocks.unwrap().running, None);
+}
+
+#[test]
+fn counters_go_below_zero_and_can_be_set_and_removed() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    creature(&mut table, home, Space(0), 0, 0, 0);
+    creature(&mut table, home, Space(1), 0, 0, 0);
+    let first = CounterTarget::Card(PlaceFrom::Space(RelSide::Same, Space(0)));
+    let second = CounterTarget::Card(PlaceFrom::Space(RelSide::Same, Space(1)));
+
+    table.script([
+        (home, ClientMsg::AddCounter(first, "Poison".to_owned(), -2)),
+        (
+            home,
+            ClientMsg::SetCounter(vec![first, second], "Charge".to_owned(), 3),
+        ),
+        (home, ClientMsg::RemoveCounter(second, POWER.to_owned())),
+    ]);
+    let row = &table.view(away).distant_row;
+    let first_card = row[Space(0)].as_ref().unwrap();
+    let second_card = row[Space(1)].as_ref().unwrap();
+    assert_eq!(first_card.counters.get("Poison"), Some(&-2));
+    assert_eq!(first_card.counters.get("Charge"), Some(&3));
+    assert_eq!(second_card.counters.get("Charge"), Some(&3));
+    assert_eq!(second_card.counters.get(POWER), None);
+
+    // Setting a counter on an empty space changes nothing
+    let empty = CounterTarget::Card(PlaceFrom::Space(RelSide::Same, Space(2)));
+    table.send(
+        home,
+        ClientMsg::SetCounter(vec![first, empty], "Charge".to_owned(), 0),
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoCountersOn(_)]
+    ));
+    let first_card = table.view(home).local_row[Space(0)].as_ref().unwrap();
+    assert_eq!(first_card.counters.get("Charge"), Some(&3));
+}
+
+#[test]
+fn players_have_counters_too() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.script([
+        (
+            home,
+            ClientMsg::CreateCounter(CounterTarget::Player(RelSide::Other), "Doom".to_owned()),
+        ),
+        (
+            home,
+            ClientMsg::AddCounter(CounterTarget::Player(RelSide::Other), "Doom".to_owned(), 2),
+        ),
+    ]);
+    assert_eq!(table.view(away).local_state.counters.get("Doom"), Some(&2));
+    assert_eq!(
+        table.view(home).distant_state.counters.get("Doom"),
+        Some(&2)
+    );
+    assert!(table.view(home).local_state.counters.is_empty());
+}
+
+#[test]
+fn tokens_vanish_once_they_leave_the_board() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+
+    table.send(
+        home,
+        ClientMsg::CreateToken("Daemon".to_owned(), PlaceTo::Hand),
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::TokenOffBoard]
+    ));
+
+    table.send(
+        home,
+        ClientMsg::CreateToken(
+            "Daemon".to_owned(),
+            PlaceTo::Space(RelSide::Same, Space(0), false),
+        ),
+    );
+    let token = table.view(away).distant_row[Space(0)].clone().unwrap();
+    assert_eq!(name(&token), Some("Daemon"));
+    assert!(token.token);
+
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Space(RelSide::Same, Space(0)),
+            to: PlaceTo::Timeline(RelSide::Same),
+        },
+    );
+    assert_eq!(table.view(home).local_state.timeline.len(), 1);
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Timeline(RelSide::Same, token.id),
+            to: PlaceTo::Hand,
+        },
+    );
+    assert!(table.view(home).local_state.timeline.is_empty());
+    assert!(table.view(home).hand.is_empty());
+}
+
+#[test]
+fn copies_keep_their_counters_and_remember_the_original() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let _away = table.sit();
+    creature(&mut table, home, Space(0), 2, 1, 3);
+    let original = table.view(home).local_row[Space(0)].clone().unwrap();
+
+    table.send(
+        home,
+        ClientMsg::CopyCard {
+            from: PlaceFrom::Space(RelSide::Same, Space(0)),
+            to: PlaceTo::Space(RelSide::Same, Space(1), false),
+        },
+    );
+    let copy = table.view(home).local_row[Space(1)].clone().unwrap();
+    assert_eq!(name(&copy), Some("Daemon"));
+    assert_eq!(copy.counters, original.counters);
+    assert!(copy.token);
+    assert!(!original.token);
+    assert_eq!(table.game.origins[&copy.id].copy_of, Some(original.id));
+
+    // Nobody knows what's in the deck, so it can't be copied
+    table.send(home, deck(&["A"]));
+    let hidden = table.game.state.get_state(Side::Home).main_deck[0];
+    let from = PlaceFrom::Deck(RelSide::Same, DeckType::Main, hidden);
+    table.send(
+        home,
+        ClientMsg::CopyCard {
+            from,
+            to: PlaceTo::Timeline(RelSide::Same),
+        },
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::CannotCopyHidden(_)]
+    ));
+    assert!(table.view(home).local_state.timeline.is_empty());
+}
+
+#[test]
+fn statuses_are_public_and_can_clear_at_the_start_of_the_owners_turn() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    creature(&mut table, home, Space(0), 1, 0, 1);
+    let space = PlaceFrom::Space(RelSide::Same, Space(0));
+    table.script([
+        (home, ClientMsg::Flip(space)),
+        (home, ClientMsg::SetStatus(space, Status::Exhausted, true)),
+        (home, ClientMsg::SetStatus(space, Status::Marked, true)),
+    ]);
+    let seen = table.view(away).distant_row[Space(0)].clone().unwrap();
+    assert!(seen.status.exhausted);
+    assert!(seen.status.marked);
+    assert!(!seen.status.summoning_sick);
+
+    let hooks = StepHook::defaults()
+        .into_iter()
+        .map(|hook| StepHook {
+            enabled: hook.action == StepAction::ClearStatus(Status::Exhausted),
+            ..hook
+        })
+        .collect();
+    table.script([
+        (home, ClientMsg::SetHooks(hooks)),
+        (home, ClientMsg::TurnSet(TurnStep::Switch)),
+    ]);
+    // Only the owner's turn clears it
+    let card = table.view(home).local_row[Space(0)].clone().unwrap();
+    assert!(card.status.exhausted);
+    table.send(away, ClientMsg::TurnSet(TurnStep::Switch));
+    let card = table.view(home).local_row[Space(0)].clone().unwrap();
+    assert!(!card.status.exhausted);
+    assert!(card.status.marked);
+
+    table.send(
+        home,
+        ClientMsg::SetStatus(
+            PlaceFrom::Space(RelSide::Same, Space(1)),
+            Status::Marked,
+            true,
+        ),
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoCardIn(_)]
+    ));
+}
+
+#[test]
+fn attachments_move_with_their_host_and_fall_off_when_it_leaves() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    creature(&mut table, home, Space(0), 1, 0, 1);
+    let host = table.view(home).local_row[Space(0)].clone().unwrap().id;
+    table.send(home, ClientMsg::CreateCard("Sword".to_owned()));
+    let sword = table.view(home).hand[0].id;
+    table.send(away, ClientMsg::CreateCard("Curse".to_owned()));
+    let curse = table.view(away).hand[0].id;
+    table.script([
+        (
+            home,
+            ClientMsg::Move {
+                from: PlaceFrom::Hand(sword),
+                to: PlaceTo::Attached(RelSide::Same, Space(0)),
+            },
+        ),
+        (
+            away,
+            ClientMsg::Move {
+                from: PlaceFrom::Hand(curse),
+                to: PlaceTo::Attached(RelSide::Other, Space(0)),
+            },
+        ),
+        (
+            home,
+            ClientMsg::Move {
+                from: PlaceFrom::Space(RelSide::Same, Space(0)),
+                to: PlaceTo::Space(RelSide::Same, Space(2), false),
+            },
+        ),
+    ]);
+    let card = table.view(away).distant_row[Space(2)].clone().unwrap();
+    let attached: Vec<_> = card.attached.iter().filter_map(name).collect();
+    assert_eq!(attached, ["Sword", "Curse"]);
+
+    // Attaching needs something else in the space
+    for to in [
+        PlaceTo::Attached(RelSide::Same, Space(1)),
+        PlaceTo::Attached(RelSide::Same, Space(2)),
+    ] {
+        table.send(
+            home,
+            ClientMsg::Move {
+                from: PlaceFrom::Space(RelSide::Same, Space(2)),
+                to,
+            },
+        );
+        assert!(matches!(
+            table.take_errors(home).as_slice(),
+            [ServerErr::NothingToAttachTo(..)]
+        ));
+    }
+
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Space(RelSide::Same, Space(2)),
+            to: PlaceTo::Hand,
+        },
+    );
+    let view = table.view(home);
+    assert_eq!(view.hand.len(), 1);
+    assert_eq!(view.hand[0].id, host);
+    let discard: Vec<_> = view.local_state.discard.iter().map(|x| x.id).collect();
+    assert_eq!(discard, [sword]);
+    let discard: Vec<_> = view.distant_state.discard.iter().map(|x| x.id).collect();
+    assert_eq!(discard, [curse]);
+}
+
+#[test]
+fn rooms_choose_how_many_spaces_each_row_has() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, ClientMsg::SetSpaces(6));
+    assert_eq!(table.view(away).local_row.len(), 6);
+    assert_eq!(table.view(away).distant_row.len(), 6);
+    creature(&mut table, home, Space(5), 1, 0, 1);
+    assert!(table.view(away).distant_row[Space(5)].is_some());
+
+    // Spaces with cards in them stay
+    table.send(away, ClientMsg::SetSpaces(3));
+    assert!(matches!(
+        table.take_errors(away).as_slice(),
+        [ServerErr::SpaceInUse(RelSide::Other, Space(5))]
+    ));
+    for spaces in [0, 9] {
+        table.send(home, ClientMsg::SetSpaces(spaces));
+        assert!(matches!(
+            table.take_errors(home).as_slice(),
+            [ServerErr::BadSpaceCount(_)]
+        ));
+    }
+    assert_eq!(table.view(home).local_row.len(), 6);
+
+    table.script([
+        (
+            home,
+            ClientMsg::Move {
+                from: PlaceFrom::Space(RelSide::Same, Space(5)),
+                to: PlaceTo::Space(RelSide::Same, Space(1), false),
+            },
+        ),
+        (away, ClientMsg::SetSpaces(2)),
+    ]);
+    assert_eq!(table.view(home).local_row.len(), 2);
+    assert!(table.view(home).local_row[Space(1)].is_some());
+
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Space(RelSide::Same, Space(1)),
+            to: PlaceTo::Space(RelSide::Other, Space(2), false),
+        },
+    );
+    assert!(matches!(
+        table.take_errors(home).as_slice(),
+        [ServerErr::NoSuchSpace(RelSide::Other, Space(2))]
+    ));
+    assert!(table.view(home).local_row[Space(1)].is_some());
+
+    // New games keep the room's board
+    table.script([
+        (home, no_opening_hand()),
+        (away, ClientMsg::AcceptStartRules),
+        (home, ClientMsg::StartGame),
+    ]);
+    assert_eq!(table.view(away).local_row.len(), 2);
+    assert_eq!(table.view(away).distant_row.iter().count(), 0);
+}
+
+#[test]
+fn floating_cards_lie_anywhere_on_the_table_and_anyone_moves_them() {
+    let mut table = Table::new();
+    let home = table.sit();
+    let away = table.sit();
+    table.send(home, ClientMsg::CreateCard("Emblem".to_owned()));
+    let emblem = table.view(home).hand[0].id;
+    table.send(
+        home,
+        ClientMsg::Move {
+            from: PlaceFrom::Hand(emblem),
+            to: PlaceTo::Floating(10, 20),
+        },
+    );
+    let (card, pos) = table.view(away).floating_cards[0].clone();
+    assert_eq!(name(&card), Some("Emblem"));
+    assert_eq!(pos, (10, 20));
+
+    table.script([
+        (
+            away,
+            ClientMsg::Move {
+                from: PlaceFrom::Floating(emblem),
+                to: PlaceTo::Floating(30, 40),
+            },
+        ),
+        (
+            away,
+            ClientMsg::AddCounter(
+                CounterTarget::Card(PlaceFrom::Floating(emblem)),
+                "TURNS".to_owned(),
+                2,
+            ),
+        ),
+        (
+            home,
+            ClientMsg::CreateToken("Marker".to_owned(), PlaceTo::Floating(0, 0)),
+        ),
+    ]);
+    let view = table.view(home);
+    assert_eq!(view.floating_cards.len(), 2);
+    let (card, pos) = &view.floating_cards[0];
+    assert_eq!(*pos, (30, 40));
+    assert_eq!(card.counters["TURNS"], 2);
+    let marker = view.floating_cards[1].0.clone();
+    assert!(marker.token);
+
+    // Tokens still vanish once they leave the table
+    table.send(
+        away,
+        ClientMsg::Move {
+            from: PlaceFrom::Floating(marker.id),
+            to: PlaceTo::Discard(RelSide::Same),
+        },
+    );
+    assert_eq!(table.view(away).floating_cards.len(), 1);
+    assert!(table.view(away).local_state.discard.is_empty());
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{ROOM, TestServer};
+use shared::{ClientMsg, Hidden, PlaceFrom, PlaceTo, RelSide, ServerErr, ServerMsg, Space};
+
+#[tokio::test]
+async fn joining_a_missing_room_fails() {
+    let mut server = TestServer::new();
+    let mut client = server.connect().await;
+
+    client.send(ClientMsg::JoinRoom("nowhere".to_owned())).await;
+
+    assert!(matches!(
+        client.recv().await,
+        Err(ServerErr::RoomDoesntExist(room)) if room == "nowhere"
+    ));
+}
+
+#[tokio::test]
+async fn two_clients_see_each_others_board() {
+    let mut server = TestServer::new();
+    let mut home = server.connect().await;
+    let mut away = server.connect().await;
+
+    home.send(ClientMsg::CreateRoom(ROOM.to_owned())).await;
+    home.recv_state_until(|_| true).await;
+    home.send(ClientMsg::PlayAs).await;
+    home.recv_state_until(|_| true).await;
+
+    away.send(ClientMsg::JoinRoom(ROOM.to_owned())).await;
+    away.recv_state_until(|_| true).await;
+    away.send(ClientMsg::PlayAs).await;
+    away.recv_state_until(|_| true).await;
+
+    home.send(ClientMsg::CreateCard("Daemon".to_owned())).await;
+    let state = home.recv_state_until(|x| !x.hand.is_empty()).await;
+    let card = state.hand[0].id;
+
+    home.send(ClientMsg::Move {
+        from: PlaceFrom::Hand(card),
+        to: PlaceTo::Space(RelSide::Same, Space(3), false),
+    })
+    .await;
+
+    let state = away
+        .recv_state_until(|x| x.distant_row[Space(3)].is_some())
+        .await;
+    let seen = state.distant_row[Space(3)].as_ref().unwrap();
+    assert!(matches!(&seen.name, Hidden::Unhidden(name) if name == "Daemon"));
+    assert!(state.hand.is_empty());
+}
+
+#[tokio::test]
+async fn creating_a_taken_room_fails() {
+    let mut server = TestServer::new();
+    let mut first = server.connect().await;
+    let mut second = server.connect().await;
+
+    first.send(ClientMsg::CreateRoom(ROOM.to_owned())).await;
+    assert!(matches!(
+        first.recv().await,
+        Ok(ServerMsg::RoomCreated | ServerMsg::JoinedRoom(..))
+    ));
+
+    second.send(ClientMsg::CreateRoom(ROOM.to_owned())).await;
+    assert!(matches!(
+        second.recv_err().await,
+        ServerErr::RoomAlreadyExist
+    ));
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{RelSide, Side};
+
+/// What happens when a player's time bank runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeoutRule {
+    /// Everyone is told once, and the game goes on.
+    Warn,
+    /// Whoever ran out hands over priority, or ends their turn if nobody else was waiting.
+    AutoPass,
+    Lose,
+}
+
+impl TimeoutRule {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            TimeoutRule::Warn => "Warn",
+            TimeoutRule::AutoPass => "Pass automatically",
+            TimeoutRule::Lose => "Lose",
+        }
+    }
+}
+
+/// Chess clock settings for a room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockRules {
+    /// How much time each player starts a game with.
+    pub bank: Duration,
+    /// Time given back to a player every time they hand over priority or end their turn.
+    pub increment: Duration,
+    pub timeout: TimeoutRule,
+}
+
+impl Default for ClockRules {
+    fn default() -> Self {
+        Self {
+            bank: Duration::from_secs(20 * 60),
+            increment: Duration::from_secs(10),
+            timeout: TimeoutRule::Warn,
+        }
+    }
+}
+
+/// The time each player has left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clocks {
+    pub home: Duration,
+    pub away: Duration,
+}
+
+impl Clocks {
+    pub fn new(rules: ClockRules) -> Self {
+        Self {
+            home: rules.bank,
+            away: rules.bank,
+        }
+    }
+    pub fn get(self, side: Side) -> Duration {
+        match side {
+            Side::Home => self.home,
+            Side::Away => self.away,
+        }
+    }
+    pub fn get_mut(&mut self, side: Side) -> &mut Duration {
+        match side {
+            Side::Home => &mut self.home,
+            Side::Away => &mut self.away,
+        }
+    }
+    /// `running` is whoever's clock is ticking right now, if anyone's is.
+    pub fn to_local(self, perspective: Side, running: Option<Side>) -> LocalClocks {
+        LocalClocks {
+            local: self.get(perspective),
+            distant: self.get(perspective.opposite()),
+            running: running.map(|x| x.relative_to(perspective)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalClocks {
+    pub local: Duration,
+    pub distant: Duration,
+    pub running: Option<RelSide>,
+}
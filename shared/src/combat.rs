@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Card, GameState, KnownTo, RelSide, Row, Side, Space};
+
+pub const POWER: &str = "POW";
+pub const DEFENSE: &str = "DEF";
+pub const HEALTH: &str = "HP";
+
+/// Who or what an attacking creature goes for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttackTarget {
+    Player,
+    /// A creature in the defender's row.
+    Space(Space),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attack {
+    pub from: Space,
+    pub target: AttackTarget,
+}
+
+/// A defending creature stepping in front of an attacker. The attacker then fights the blocker
+/// instead of whatever it was going for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    pub blocker: Space,
+    pub attacker: Space,
+}
+
+/// An attack in progress. Damage is only dealt once both players confirm, and changing the
+/// attacks or blocks takes both confirmations back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Combat {
+    pub attacker: Side,
+    pub attacks: Vec<Attack>,
+    pub blocks: Vec<Block>,
+    pub confirmed: KnownTo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HitTarget {
+    Card(Side, Space),
+    Player(Side),
+}
+
+/// Damage about to be dealt to a creature or a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hit {
+    pub target: HitTarget,
+    pub damage: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LocalHitTarget {
+    Card(RelSide, Space),
+    Player(RelSide),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalHit {
+    pub target: LocalHitTarget,
+    pub damage: usize,
+}
+
+/// The combat as a player sees it, along with the damage it would deal right now.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalCombat {
+    pub attacker: RelSide,
+    pub attacks: Vec<Attack>,
+    pub blocks: Vec<Block>,
+    pub local_confirmed: bool,
+    pub distant_confirmed: bool,
+    pub hits: Vec<LocalHit>,
+}
+
+impl Hit {
+    pub fn relative_to(self, local: Side) -> LocalHit {
+        LocalHit {
+            target: match self.target {
+                HitTarget::Card(side, space) => {
+                    LocalHitTarget::Card(side.relative_to(local), space)
+                }
+                HitTarget::Player(side) => LocalHitTarget::Player(side.relative_to(local)),
+            },
+            damage: self.damage,
+        }
+    }
+}
+
+/// Counters below zero count as zero in a fight.
+fn counter(card: &Card, name: &str) -> usize {
+    let value = card.counters.get(name).copied().unwrap_or_default();
+    usize::try_from(value).unwrap_or_default()
+}
+
+/// How much one creature hurts another. Defense soaks up power point for point.
+pub fn damage(from: &Card, to: &Card) -> usize {
+    counter(from, POWER).saturating_sub(counter(to, DEFENSE))
+}
+
+impl Combat {
+    pub fn new(attacker: Side, attacks: Vec<Attack>) -> Self {
+        Self {
+            attacker,
+            attacks,
+            blocks: vec![],
+            confirmed: KnownTo::default(),
+        }
+    }
+    pub fn defender(&self) -> Side {
+        self.attacker.opposite()
+    }
+    /// Every attacker has to be a creature attacking once, and creatures can only be attacked
+    /// if they're there.
+    pub fn valid_attacks(attacks: &[Attack], attacking: &Row, defending: &Row) -> bool {
+        attacks.iter().enumerate().all(|(idx, attack)| {
+            attacking.get(attack.from).is_some()
+                && !attacks[..idx].iter().any(|x| x.from == attack.from)
+                && match attack.target {
+                    AttackTarget::Player => true,
+                    AttackTarget::Space(space) => defending.get(space).is_some(),
+                }
+        })
+    }
+    /// Every blocker has to be a creature blocking an attacker nobody else blocks yet.
+    pub fn valid_blocks(&self, blocks: &[Block], defending: &Row) -> bool {
+        blocks.iter().enumerate().all(|(idx, block)| {
+            defending.get(block.blocker).is_some()
+                && self.attacks.iter().any(|x| x.from == block.attacker)
+                && !blocks[..idx]
+                    .iter()
+                    .any(|x| x.blocker == block.blocker || x.attacker == block.attacker)
+        })
+    }
+    /// Everything that would get hit, worked out from the counters before any damage is dealt.
+    /// Creatures that fight hit each other back. Hits on the same target are added up.
+    pub fn hits(&self, state: &GameState) -> Vec<Hit> {
+        let attacking = state.get_row(self.attacker);
+        let defending = state.get_row(self.defender());
+        let mut hits: Vec<Hit> = vec![];
+        let mut hit = |target, damage| {
+            if damage == 0 {
+                return;
+            }
+            match hits.iter_mut().find(|x| x.target == target) {
+                Some(hit) => hit.damage += damage,
+                None => hits.push(Hit { target, damage }),
+            }
+        };
+
+        for attack in &self.attacks {
+            let Some(card) = attacking.get(attack.from) else {
+                continue;
+            };
+            let blocker = self
+                .blocks
+                .iter()
+                .find(|x| x.attacker == attack.from)
+                .map(|x| x.blocker);
+            let fights = match (blocker, attack.target) {
+                (Some(space), _) | (None, AttackTarget::Space(space)) => space,
+                (None, AttackTarget::Player) => {
+                    hit(HitTarget::Player(self.defender()), counter(card, POWER));
+                    continue;
+                }
+            };
+            let Some(other) = defending.get(fights) else {
+                continue;
+            };
+            hit(
+                HitTarget::Card(self.defender(), fights),
+                damage(card, other),
+            );
+            hit(
+                HitTarget::Card(self.attacker, attack.from),
+                damage(other, card),
+            );
+        }
+        hits
+    }
+    pub fn to_local(&self, perspective: Side, state: &GameState) -> LocalCombat {
+        LocalCombat {
+            attacker: self.attacker.relative_to(perspective),
+            attacks: self.attacks.clone(),
+            blocks: self.blocks.clone(),
+            local_confirmed: self.confirmed.contains(perspective),
+            distant_confirmed: self.confirmed.contains(perspective.opposite()),
+            hits: self
+                .hits(state)
+                .into_iter()
+                .map(|x| x.relative_to(perspective))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CardId, Hidden, NamedCardId, RelSide, Side, StepAction, TurnStep, Viewer, Visibility, Zone,
+};
+
+/// Something random or automatic that happened in the room. These are resolved by the server so
+/// nobody has to trust anyone else's dice.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HistoryEvent {
+    Roll {
+        sides: u32,
+        results: Vec<u32>,
+    },
+    CoinFlip {
+        heads: bool,
+    },
+    /// `position` counts from the top of decks and from the left everywhere else.
+    RandomCard {
+        owner: Side,
+        zone: Zone,
+        position: usize,
+        card: CardId,
+        /// Who could know the card when it was picked.
+        visibility: Visibility,
+    },
+    /// A step action that ran on its own.
+    Automated {
+        step: TurnStep,
+        action: StepAction,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub by: Side,
+    pub event: HistoryEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LocalHistoryEvent {
+    Roll {
+        sides: u32,
+        results: Vec<u32>,
+    },
+    CoinFlip {
+        heads: bool,
+    },
+    RandomCard {
+        owner: RelSide,
+        zone: Zone,
+        position: usize,
+        card: Hidden<NamedCardId>,
+    },
+    Automated {
+        step: TurnStep,
+        action: StepAction,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalHistoryEntry {
+    pub by: RelSide,
+    pub event: LocalHistoryEvent,
+}
+
+impl HistoryEntry {
+    pub fn to_local(&self, viewer: Viewer, ids: &BTreeMap<CardId, String>) -> LocalHistoryEntry {
+        // Spectators watch from Home's seat, same as with the rest of the state.
+        let perspective = match viewer {
+            Viewer::Player(side) => side,
+            Viewer::Spectator => Side::Home,
+        };
+        let event = match &self.event {
+            HistoryEvent::Roll { sides, results } => LocalHistoryEvent::Roll {
+                sides: *sides,
+                results: results.clone(),
+            },
+            HistoryEvent::CoinFlip { heads } => LocalHistoryEvent::CoinFlip { heads: *heads },
+            HistoryEvent::RandomCard {
+                owner,
+                zone,
+                position,
+                card,
+                visibility,
+            } => LocalHistoryEvent::RandomCard {
+                owner: owner.relative_to(perspective),
+                zone: *zone,
+                position: *position,
+                card: if viewer.sees(*visibility, Some(*owner)) {
+                    Hidden::Unhidden(NamedCardId {
+                        name: ids.get(card).unwrap().clone(),
+                        id: *card,
+                    })
+                } else {
+                    Hidden::Hidden
+                },
+            },
+            HistoryEvent::Automated { step, action } => LocalHistoryEvent::Automated {
+                step: *step,
+                action: action.clone(),
+            },
+        };
+        LocalHistoryEntry {
+            by: self.by.relative_to(perspective),
+            event,
+        }
+    }
+}
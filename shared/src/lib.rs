@@ -5,6 +5,21 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
+mod clock;
+mod combat;
+mod history;
+mod status;
+mod visibility;
+
+pub use clock::{ClockRules, Clocks, LocalClocks, TimeoutRule};
+pub use combat::{
+    Attack, AttackTarget, Block, Combat, DEFENSE, HEALTH, Hit, HitTarget, LocalCombat, LocalHit,
+    LocalHitTarget, POWER, damage,
+};
+pub use history::{HistoryEntry, HistoryEvent, LocalHistoryEntry, LocalHistoryEvent};
+pub use status::{Status, StatusFlags};
+pub use visibility::{KnownTo, Viewer, Visibility, Zone};
+
 // This is my single worst piece of code.
 // If you don't know how to read this, don't worry. You won't.
 // Just turn around while you can.
@@ -24,23 +39,131 @@ pub enum ServerMsg {
     },
     UpdateDiscard(RelSide, Vec<NamedCardId>),
     UpdateTimeline(RelSide, Vec<LocalCard>),
-    BeginSearch(Vec<NamedCardId>),
+    /// The deck the player is searching, sent again whenever it changes.
+    BeginSearch {
+        side: RelSide,
+        deck: DeckType,
+        cards: Vec<NamedCardId>,
+    },
     UpdateState(Box<LocalState>),
     RoomCreated,
     JoinedRoom(Box<LocalState>),
+    GameOver {
+        /// None for a draw
+        winner: Option<RelSide>,
+        reason: GameOverReason,
+    },
+    /// A roll, flip, random pick or automatic step action, sent to everyone in the room.
+    History(LocalHistoryEntry),
+    /// The top of a deck, top card first. Only sent to whoever asked to look.
+    LookAtTop {
+        side: RelSide,
+        deck: DeckType,
+        cards: Vec<NamedCardId>,
+    },
+    /// Someone is looking at the top of a deck. Only says how many cards.
+    LookedAtTop {
+        by: RelSide,
+        side: RelSide,
+        deck: DeckType,
+        n: usize,
+    },
+    /// Someone finished searching a deck.
+    Searched {
+        by: RelSide,
+        side: RelSide,
+        deck: DeckType,
+        shuffled: bool,
+    },
+    /// Someone finished looking at the top of a deck. Only says how many cards went where.
+    TopArranged {
+        by: RelSide,
+        side: RelSide,
+        deck: DeckType,
+        counts: ArrangedCounts,
+    },
+    /// Cards another player showed us.
+    Revealed {
+        by: RelSide,
+        cards: Vec<NamedCardId>,
+    },
+    /// The damage a combat dealt, once both players confirmed it.
+    CombatResolved(Vec<LocalHit>),
+    /// The clocks as they tick. Sent every second or so while one is running.
+    Clocks(LocalClocks),
+    /// Someone's time bank ran out.
+    OutOfTime(RelSide),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ServerErr {
     RoomDoesntExist(String),
-    NotInGame { action: String },
+    NotInGame {
+        action: String,
+    },
     NotInSide,
     NoPlayerInSide(Side),
     NoCardIn(PlaceFrom),
+    /// Cards in hands and decks can't have counters.
+    NoCountersOn(CounterTarget),
+    /// Tokens can only be made on the board.
+    TokenOffBoard,
+    CannotCopyHidden(PlaceFrom),
+    /// Players can only peek at cards on their side of the table, or at cards they own.
+    CannotPeek(PlaceFrom),
+    /// Same as [`ServerErr::CannotPeek`], for revealing.
+    CannotReveal(PlaceFrom),
+    /// Cards can only be attached to a card that's in the space, and not to themselves.
+    NothingToAttachTo(RelSide, Space),
+    /// The row isn't that long.
+    NoSuchSpace(RelSide, Space),
+    /// Rows need at least one space, and no more than [`MAX_SPACES`].
+    BadSpaceCount(usize),
+    /// A row can't lose a space that still has a card in it.
+    SpaceInUse(RelSide, Space),
     SideOccupied(Side),
     GameIsFull,
-    AlreadyInGame { action: String },
+    AlreadyInGame {
+        action: String,
+    },
     RoomAlreadyExist,
+    GameIsOver,
+    /// Games have to be dealt with [`ClientMsg::StartGame`] before they can end.
+    GameNotStarted,
+    GameNotOver,
+    NoDrawOffered,
+    NoStartRulesProposed,
+    /// A new game can't be dealt, and the room's rules can't change, until the current one is over.
+    GameInProgress,
+    /// Matches are at least one game long.
+    MatchTooShort,
+    /// The match length is fixed once a game of the match has been recorded.
+    MatchUnderway,
+    NotMulliganing,
+    /// The turn can't move on until everyone kept their opening hand.
+    StillMulliganing,
+    /// Dice need at least one side, and there has to be at least one of them.
+    BadRoll,
+    NoCardsIn(RelSide, Zone),
+    NotLooking,
+    /// Every card looked at has to go somewhere, exactly once.
+    BadArrangement,
+    /// The looked at cards aren't on top of the deck anymore. Look again.
+    DeckChangedSinceLook,
+    /// Looking at every card of a deck is a search, see [`ClientMsg::RequestSearch`].
+    LookAtWholeDeck,
+    SearchDenied,
+    NoSearchRequest,
+    NotSearching,
+    NotYourTurn,
+    /// Strict rooms only let whoever holds priority act.
+    NoPriority,
+    /// Attacks can only be declared during the combat step.
+    NotCombatStep,
+    NoCombat,
+    /// Attackers and blockers have to be creatures in the row, each used once.
+    BadAttack,
+    BadBlock,
 }
 
 impl ServerMsg {
@@ -50,23 +173,43 @@ impl ServerMsg {
             ServerMsg::UpdateSpaces { .. } => true,
             ServerMsg::UpdateDiscard(..) => true,
             ServerMsg::UpdateTimeline(..) => true,
-            ServerMsg::BeginSearch(..) => true,
+            ServerMsg::BeginSearch { .. } => true,
             ServerMsg::UpdateState(..) => true,
             ServerMsg::RoomCreated => false,
             ServerMsg::JoinedRoom(..) => false,
+            ServerMsg::GameOver { .. } => true,
+            ServerMsg::History(..) => true,
+            ServerMsg::LookAtTop { .. } => true,
+            ServerMsg::LookedAtTop { .. } => true,
+            ServerMsg::TopArranged { .. } => true,
+            ServerMsg::Searched { .. } => true,
+            ServerMsg::Revealed { .. } => true,
+            ServerMsg::CombatResolved(..) => true,
+            ServerMsg::Clocks(..) => true,
+            ServerMsg::OutOfTime(..) => true,
         }
     }
 
     pub fn get_name(&self) -> &'static str {
         match self {
             ServerMsg::UpdateHand(..) => "update hand",
-            ServerMsg::UpdateSpaces { home_row, away_row } => "update spaces",
-            ServerMsg::UpdateDiscard(side, vec) => "update discard",
-            ServerMsg::UpdateTimeline(side, vec) => "update timeline",
-            ServerMsg::BeginSearch(vec) => "begin search",
-            ServerMsg::UpdateState(local_state) => "update state",
+            ServerMsg::UpdateSpaces { .. } => "update spaces",
+            ServerMsg::UpdateDiscard(..) => "update discard",
+            ServerMsg::UpdateTimeline(..) => "update timeline",
+            ServerMsg::BeginSearch { .. } => "begin search",
+            ServerMsg::UpdateState(..) => "update state",
             ServerMsg::RoomCreated => "room created",
-            ServerMsg::JoinedRoom(local_state) => "join room",
+            ServerMsg::JoinedRoom(..) => "join room",
+            ServerMsg::GameOver { .. } => "game over",
+            ServerMsg::History(..) => "history entry",
+            ServerMsg::LookAtTop { .. } => "look at top",
+            ServerMsg::LookedAtTop { .. } => "looked at top",
+            ServerMsg::TopArranged { .. } => "arranged top",
+            ServerMsg::Searched { .. } => "searched",
+            ServerMsg::Revealed { .. } => "revealed",
+            ServerMsg::CombatResolved(..) => "combat resolved",
+            ServerMsg::Clocks(..) => "clocks",
+            ServerMsg::OutOfTime(..) => "out of time",
         }
     }
 }
@@ -74,22 +217,119 @@ impl ServerMsg {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ClientMsg {
     Draw(RelSide, DeckType),
-    Move { from: PlaceFrom, to: PlaceTo },
+    Move {
+        from: PlaceFrom,
+        to: PlaceTo,
+    },
     Shuffle(DeckType),
-    RequestSearch(DeckType),
+    /// Searching the other player's deck needs them to agree first.
+    RequestSearch(RelSide, DeckType),
+    /// Lets the other player search your deck, or not.
+    AnswerSearch(bool),
     Update,
     CreateRoom(String),
     SetDeck(DeckType, VecDeque<String>),
     JoinRoom(String),
     PlayAs,
-    AddCounter(PlaceFrom, String, bool),
-    CreateCounter(PlaceFrom, String),
-    FinishSearch,
+    /// Adds to a counter, which can go below zero. Missing counters start at zero.
+    AddCounter(CounterTarget, String, isize),
+    CreateCounter(CounterTarget, String),
+    /// Sets a counter to the same value on every target at once.
+    SetCounter(Vec<CounterTarget>, String, isize),
+    RemoveCounter(CounterTarget, String),
+    /// Turns a status on or off for a card on the board.
+    SetStatus(PlaceFrom, Status, bool),
+    FinishSearch {
+        shuffle: bool,
+    },
     LeaveRoom,
     AddBlood(RelSide, bool),
-    AddHealth(bool),
+    AddHealth(RelSide, isize),
     TurnSet(TurnStep),
     CreateCard(String),
+    /// Makes a token straight onto a space or the timeline. Tokens vanish once they leave
+    /// the board.
+    CreateToken(String, PlaceTo),
+    /// Makes a token copy of a card, counters included. Only cards the player can see can be
+    /// copied.
+    CopyCard {
+        from: PlaceFrom,
+        to: PlaceTo,
+    },
+    /// Puts a card into its owner's hand, wherever it is and whoever controls it.
+    ReturnToOwner(PlaceFrom),
+    /// Turns a card in a space or timeline face up or face down.
+    Flip(PlaceFrom),
+    /// Looks at a face-down card without turning it over.
+    Peek(PlaceFrom),
+    /// Shows cards to the other player, or to everyone. Face-down cards stay known to whoever
+    /// saw them, and revealed hand cards stay known until they leave the hand.
+    Reveal {
+        cards: Vec<PlaceFrom>,
+        to: RevealTarget,
+    },
+    Concede,
+    /// Changes how many games the current match lasts.
+    SetMatchLength(usize),
+    OfferDraw,
+    AcceptDraw,
+    /// Starts the next game of the match once this one is over, or a new match if this one
+    /// is decided. Decks are rebuilt from the latest deck lists, so sideboard with `SetDeck`
+    /// before this.
+    Rematch,
+    /// Proposes how games in the room are set up. They only change once the other player
+    /// accepts, or right away if nobody else is seated.
+    SetStartRules(StartRules),
+    /// Agrees to the start rules the other player proposed.
+    AcceptStartRules,
+    /// Deals the current game from scratch: shuffles both decks, picks who starts at random
+    /// and deals opening hands. Not while a game is being played.
+    StartGame,
+    /// Shuffles the opening hand back and draws a new one.
+    Mulligan,
+    KeepHand,
+    Roll {
+        sides: u32,
+        count: u32,
+    },
+    FlipCoin,
+    /// Picks a random card in a zone without moving it.
+    PickRandom(RelSide, Zone),
+    /// Looking at the other player's deck needs them to agree first, like a search.
+    LookAtTop {
+        deck: DeckType,
+        n: usize,
+        side: RelSide,
+    },
+    /// Puts away the cards from [`ClientMsg::LookAtTop`].
+    ArrangeTop(TopArrangement),
+    DrawMany(RelSide, DeckType, usize),
+    /// Puts cards from the top of a deck into its owner's discard.
+    Mill(RelSide, DeckType, usize),
+    /// Shows the top card of a deck to everyone until it stops being the top card.
+    RevealTop(RelSide, DeckType),
+    /// Keeps the top card of one of your decks revealed, whatever it is.
+    SetPlayWithTopRevealed(DeckType, bool),
+    /// Replaces the step actions that run on your turns.
+    SetHooks(Vec<StepHook>),
+    /// Turns strict mode on or off for the room. Only allowed between games.
+    SetStrict(bool),
+    /// Lets the other player respond. They pass it back when they're done.
+    PassPriority,
+    /// Plays with a chess clock, or without one. Both clocks start over. Only allowed between
+    /// games.
+    SetClock(Option<ClockRules>),
+    /// Changes how many spaces both rows have. Spaces are added or taken away at the end.
+    SetSpaces(usize),
+    /// Declares which creatures attack and what they go for. Declaring again starts the combat
+    /// over.
+    DeclareAttack(Vec<Attack>),
+    /// Replaces the defender's blocks.
+    DeclareBlocks(Vec<Block>),
+    /// Agrees to the combat as it is. Damage is dealt once both players confirm.
+    ConfirmCombat,
+    /// Takes back the attack.
+    CancelCombat,
 }
 
 impl ClientMsg {
@@ -106,34 +346,172 @@ impl ClientMsg {
             ClientMsg::JoinRoom(..) => false,
             ClientMsg::AddCounter(..) => true,
             ClientMsg::CreateCounter(..) => true,
-            ClientMsg::FinishSearch => true,
+            ClientMsg::SetCounter(..) => true,
+            ClientMsg::RemoveCounter(..) => true,
+            ClientMsg::SetStatus(..) => true,
+            ClientMsg::FinishSearch { .. } => true,
+            ClientMsg::AnswerSearch(..) => true,
             ClientMsg::LeaveRoom => true,
             ClientMsg::AddBlood(..) => true,
             ClientMsg::TurnSet(..) => true,
             ClientMsg::AddHealth(..) => true,
             ClientMsg::CreateCard(..) => true,
+            ClientMsg::CreateToken(..) => true,
+            ClientMsg::CopyCard { .. } => true,
+            ClientMsg::ReturnToOwner(..) => true,
+            ClientMsg::Flip(..) => true,
+            ClientMsg::Peek(..) => true,
+            ClientMsg::Reveal { .. } => true,
+            ClientMsg::Concede => true,
+            ClientMsg::SetMatchLength(..) => true,
+            ClientMsg::OfferDraw => true,
+            ClientMsg::AcceptDraw => true,
+            ClientMsg::Rematch => true,
+            ClientMsg::SetStartRules(..) => true,
+            ClientMsg::AcceptStartRules => true,
+            ClientMsg::StartGame => true,
+            ClientMsg::Mulligan => true,
+            ClientMsg::KeepHand => true,
+            ClientMsg::Roll { .. } => true,
+            ClientMsg::FlipCoin => true,
+            ClientMsg::PickRandom(..) => true,
+            ClientMsg::LookAtTop { .. } => true,
+            ClientMsg::ArrangeTop(..) => true,
+            ClientMsg::DrawMany(..) => true,
+            ClientMsg::Mill(..) => true,
+            ClientMsg::RevealTop(..) => true,
+            ClientMsg::SetPlayWithTopRevealed(..) => true,
+            ClientMsg::SetHooks(..) => true,
+            ClientMsg::SetStrict(..) => true,
+            ClientMsg::PassPriority => true,
+            ClientMsg::SetClock(..) => true,
+            ClientMsg::SetSpaces(..) => true,
+            ClientMsg::DeclareAttack(..) => true,
+            ClientMsg::DeclareBlocks(..) => true,
+            ClientMsg::ConfirmCombat => true,
+            ClientMsg::CancelCombat => true,
         }
     }
 
     pub fn get_name(&self) -> &'static str {
         match self {
-            ClientMsg::Draw(rel_side, deck_type) => "draw",
-            ClientMsg::Move { from, to } => "move",
-            ClientMsg::Shuffle(deck_type) => "shuffle",
+            ClientMsg::Draw(..) => "draw",
+            ClientMsg::Move { .. } => "move",
+            ClientMsg::Shuffle(..) => "shuffle",
             ClientMsg::RequestSearch(..) => "request search",
             ClientMsg::Update => "update",
             ClientMsg::CreateRoom(..) => "create room",
-            ClientMsg::SetDeck(deck_type, vec_deque) => "set deck",
+            ClientMsg::SetDeck(..) => "set deck",
             ClientMsg::JoinRoom(_) => "join room",
             ClientMsg::PlayAs => "play in game",
-            ClientMsg::AddCounter(..) => "add one to counter",
+            ClientMsg::AddCounter(..) => "add to counter",
             ClientMsg::CreateCounter(..) => "create new counter",
-            ClientMsg::FinishSearch => "done searching",
+            ClientMsg::SetCounter(..) => "set counter",
+            ClientMsg::RemoveCounter(..) => "remove counter",
+            ClientMsg::SetStatus(..) => "set status",
+            ClientMsg::FinishSearch { .. } => "done searching",
+            ClientMsg::AnswerSearch(..) => "answer search request",
             ClientMsg::LeaveRoom => "leaving room",
-            ClientMsg::AddBlood(rel_side, _) => "add blood",
+            ClientMsg::AddBlood(..) => "add blood",
             ClientMsg::TurnSet(..) => "end turn",
-            ClientMsg::AddHealth(_) => "add health",
+            ClientMsg::AddHealth(..) => "add health",
             ClientMsg::CreateCard(_) => "create card",
+            ClientMsg::CreateToken(..) => "create token",
+            ClientMsg::CopyCard { .. } => "copy card",
+            ClientMsg::ReturnToOwner(_) => "return card to owner",
+            ClientMsg::Flip(_) => "flip card",
+            ClientMsg::Peek(_) => "peek at card",
+            ClientMsg::Reveal { .. } => "reveal cards",
+            ClientMsg::Concede => "concede",
+            ClientMsg::SetMatchLength(..) => "set match length",
+            ClientMsg::OfferDraw => "offer draw",
+            ClientMsg::AcceptDraw => "accept draw",
+            ClientMsg::Rematch => "rematch",
+            ClientMsg::SetStartRules(..) => "set start rules",
+            ClientMsg::AcceptStartRules => "accept start rules",
+            ClientMsg::StartGame => "start game",
+            ClientMsg::Mulligan => "mulligan",
+            ClientMsg::KeepHand => "keep hand",
+            ClientMsg::Roll { .. } => "roll dice",
+            ClientMsg::FlipCoin => "flip coin",
+            ClientMsg::PickRandom(..) => "pick random card",
+            ClientMsg::LookAtTop { .. } => "look at top",
+            ClientMsg::ArrangeTop(..) => "arrange top",
+            ClientMsg::DrawMany(..) => "draw cards",
+            ClientMsg::Mill(..) => "mill",
+            ClientMsg::RevealTop(..) => "reveal top card",
+            ClientMsg::SetPlayWithTopRevealed(..) => "play with top revealed",
+            ClientMsg::SetHooks(..) => "set step actions",
+            ClientMsg::SetStrict(..) => "set strict mode",
+            ClientMsg::PassPriority => "pass priority",
+            ClientMsg::SetClock(..) => "set clock",
+            ClientMsg::SetSpaces(..) => "set spaces",
+            ClientMsg::DeclareAttack(..) => "declare attack",
+            ClientMsg::DeclareBlocks(..) => "declare blocks",
+            ClientMsg::ConfirmCombat => "confirm combat",
+            ClientMsg::CancelCombat => "cancel combat",
+        }
+    }
+
+    /// Whether strict rooms only let this through from whoever holds priority. Answers to the
+    /// other player, settings and looking at your own cards never need it.
+    pub fn needs_priority(&self) -> bool {
+        match self {
+            ClientMsg::Draw(..) => true,
+            ClientMsg::Move { .. } => true,
+            ClientMsg::Shuffle(..) => true,
+            ClientMsg::RequestSearch(..) => true,
+            ClientMsg::Update => false,
+            ClientMsg::SetDeck(..) => false,
+            ClientMsg::PlayAs => false,
+            ClientMsg::CreateRoom(..) => false,
+            ClientMsg::JoinRoom(..) => false,
+            ClientMsg::AddCounter(..) => true,
+            ClientMsg::CreateCounter(..) => true,
+            ClientMsg::SetCounter(..) => true,
+            ClientMsg::RemoveCounter(..) => true,
+            ClientMsg::SetStatus(..) => true,
+            ClientMsg::FinishSearch { .. } => false,
+            ClientMsg::AnswerSearch(..) => false,
+            ClientMsg::LeaveRoom => false,
+            ClientMsg::AddBlood(..) => true,
+            ClientMsg::TurnSet(..) => true,
+            ClientMsg::AddHealth(..) => true,
+            ClientMsg::CreateCard(..) => true,
+            ClientMsg::CreateToken(..) => true,
+            ClientMsg::CopyCard { .. } => true,
+            ClientMsg::ReturnToOwner(..) => true,
+            ClientMsg::Flip(..) => true,
+            ClientMsg::Peek(..) => false,
+            ClientMsg::Reveal { .. } => true,
+            ClientMsg::Concede => false,
+            ClientMsg::SetMatchLength(..) => false,
+            ClientMsg::OfferDraw => false,
+            ClientMsg::AcceptDraw => false,
+            ClientMsg::Rematch => false,
+            ClientMsg::SetStartRules(..) => false,
+            ClientMsg::AcceptStartRules => false,
+            ClientMsg::StartGame => false,
+            ClientMsg::Mulligan => false,
+            ClientMsg::KeepHand => false,
+            ClientMsg::Roll { .. } => true,
+            ClientMsg::FlipCoin => true,
+            ClientMsg::PickRandom(..) => true,
+            ClientMsg::LookAtTop { .. } => true,
+            ClientMsg::ArrangeTop(..) => false,
+            ClientMsg::DrawMany(..) => true,
+            ClientMsg::Mill(..) => true,
+            ClientMsg::RevealTop(..) => true,
+            ClientMsg::SetPlayWithTopRevealed(..) => false,
+            ClientMsg::SetHooks(..) => false,
+            ClientMsg::SetStrict(..) => false,
+            ClientMsg::PassPriority => true,
+            ClientMsg::SetClock(..) => false,
+            ClientMsg::SetSpaces(..) => false,
+            ClientMsg::DeclareAttack(..) => true,
+            ClientMsg::DeclareBlocks(..) => false,
+            ClientMsg::ConfirmCombat => false,
+            ClientMsg::CancelCombat => true,
         }
     }
 }
@@ -153,15 +531,20 @@ pub enum PlaceFrom {
     Aside(CardId),
     Timeline(RelSide, CardId),
     Deck(RelSide, DeckType, CardId),
+    /// A card attached to whatever is in the space.
+    Attached(RelSide, Space, CardId),
+    /// A card lying somewhere on the table, outside the rows.
+    Floating(CardId),
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum Space {
-    First = 0,
-    Second = 1,
-    Third = 2,
-    Fourth = 3,
-}
+/// A space in a row, counting from zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Space(pub usize);
+
+/// How many spaces a row has unless the room picks something else.
+pub const DEFAULT_SPACES: usize = 4;
+/// The most spaces a room can give its rows.
+pub const MAX_SPACES: usize = 8;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 /// Sinde Liberate is not a place, this does not accurately represent Bloodless move destinations.
@@ -174,102 +557,224 @@ pub enum PlaceTo {
     Deck(DeckTo, RelSide, DeckType),
     /// While not technically a place, this works
     Liberate,
+    /// Under the card in the space, after anything already attached to it.
+    Attached(RelSide, Space),
+    /// Anywhere on the table, in points from its top left corner.
+    Floating(usize, usize),
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Move {}
 
+/// Where the cards someone looked at go. Each list is in order from top to bottom, so the last
+/// card in `bottom` ends up at the very bottom of the deck. Cards put in the hand go to whoever
+/// looked, and discarded cards go to the deck owner's discard.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopArrangement {
+    pub top: Vec<CardId>,
+    pub bottom: Vec<CardId>,
+    pub hand: Vec<CardId>,
+    pub discard: Vec<CardId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArrangedCounts {
+    pub top: usize,
+    pub bottom: usize,
+    pub hand: usize,
+    pub discard: usize,
+}
+
+impl TopArrangement {
+    pub fn counts(&self) -> ArrangedCounts {
+        ArrangedCounts {
+            top: self.top.len(),
+            bottom: self.bottom.len(),
+            hand: self.hand.len(),
+            discard: self.discard.len(),
+        }
+    }
+    /// Every card in the arrangement, in no particular order.
+    pub fn cards(&self) -> impl Iterator<Item = CardId> + '_ {
+        self.top
+            .iter()
+            .chain(&self.bottom)
+            .chain(&self.hand)
+            .chain(&self.discard)
+            .copied()
+    }
+}
+
+/// The cards someone is looking at from the top of a deck, top card first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookAt {
+    pub owner: Side,
+    pub deck: DeckType,
+    pub cards: Vec<CardId>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RowBase<T> {
-    first: Option<T>,
-    second: Option<T>,
-    third: Option<T>,
-    fourth: Option<T>,
+    spaces: Vec<Option<T>>,
 }
 
 impl<T> Default for RowBase<T> {
     fn default() -> Self {
-        Self {
-            first: None,
-            second: None,
-            third: None,
-            fourth: None,
-        }
+        Self::new(DEFAULT_SPACES)
     }
 }
 
 pub type Row = RowBase<Card>;
 pub type LocalRow = RowBase<LocalCard>;
 
+/// Like a slice, indexing past the last space panics. Use [`RowBase::get`] for spaces that
+/// might not exist.
 impl<T> Index<Space> for RowBase<T> {
     type Output = Option<T>;
 
     fn index(&self, index: Space) -> &Self::Output {
-        match index {
-            Space::First => &self.first,
-            Space::Second => &self.second,
-            Space::Third => &self.third,
-            Space::Fourth => &self.fourth,
-        }
+        &self.spaces[index.0]
     }
 }
 
 impl<T> IndexMut<Space> for RowBase<T> {
     fn index_mut(&mut self, index: Space) -> &mut Self::Output {
-        match index {
-            Space::First => &mut self.first,
-            Space::Second => &mut self.second,
-            Space::Third => &mut self.third,
-            Space::Fourth => &mut self.fourth,
-        }
+        &mut self.spaces[index.0]
     }
 }
 
 impl Row {
-    pub fn to_local(self, ids: &BTreeMap<CardId, String>) -> LocalRow {
+    pub fn to_local(self, viewer: Viewer, owner: Side, ids: &BTreeMap<CardId, String>) -> LocalRow {
+        let zone = Zone::Row.visibility();
+        let local = |x: Card| x.to_local(viewer, zone, Some(owner), ids);
         LocalRow {
-            first: self.first.map(|x| x.to_local(ids)),
-            second: self.second.map(|x| x.to_local(ids)),
-            third: self.third.map(|x| x.to_local(ids)),
-            fourth: self.fourth.map(|x| x.to_local(ids)),
+            spaces: self.spaces.into_iter().map(|x| x.map(local)).collect(),
         }
     }
 }
 
 impl<T> RowBase<T> {
-    pub fn get(&self, idx: Space) -> Option<&T> {
-        match idx {
-            Space::First => self.first.as_ref(),
-            Space::Second => self.second.as_ref(),
-            Space::Third => self.third.as_ref(),
-            Space::Fourth => self.fourth.as_ref(),
+    /// An empty row with `len` spaces.
+    pub fn new(len: usize) -> Self {
+        Self {
+            spaces: (0..len).map(|_| None).collect(),
         }
     }
+    pub fn len(&self) -> usize {
+        self.spaces.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.spaces.is_empty()
+    }
+    /// Every space in the row, in order.
+    pub fn spaces(&self) -> impl DoubleEndedIterator<Item = Space> + use<T> {
+        (0..self.len()).map(Space)
+    }
+    pub fn has_space(&self, idx: Space) -> bool {
+        idx.0 < self.len()
+    }
+    pub fn get(&self, idx: Space) -> Option<&T> {
+        self.spaces.get(idx.0)?.as_ref()
+    }
     pub fn get_mut(&mut self, idx: Space) -> Option<&mut T> {
-        match idx {
-            Space::First => self.first.as_mut(),
-            Space::Second => self.second.as_mut(),
-            Space::Third => self.third.as_mut(),
-            Space::Fourth => self.fourth.as_mut(),
+        self.spaces.get_mut(idx.0)?.as_mut()
+    }
+    /// The space itself, whether there's a card in it or not. None if the row is too short.
+    pub fn slot_mut(&mut self, idx: Space) -> Option<&mut Option<T>> {
+        self.spaces.get_mut(idx.0)
+    }
+    /// Every card in the row, first space first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.spaces.iter().flatten()
+    }
+    /// Every card in the row, first space first.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.spaces.iter_mut().flatten()
+    }
+    /// Adds or removes spaces at the end of the row. Fails without changing anything if a
+    /// space that would go away still has a card in it.
+    pub fn resize(&mut self, len: usize) -> Result<(), Space> {
+        if let Some(idx) = self.spaces.iter().skip(len).position(Option::is_some) {
+            return Err(Space(len + idx));
         }
+        self.spaces.resize_with(len, || None);
+        Ok(())
     }
 }
 
 impl Card {
-    pub fn to_local(self, ids: &BTreeMap<CardId, String>) -> LocalCard {
-        let name = if self.backside {
-            Hidden::Hidden
+    /// Face-down cards are only known to whoever looked at them, no matter which zone they are in.
+    pub fn visibility(&self, zone: Visibility) -> Visibility {
+        if self.backside {
+            Visibility::KnownTo(self.known_to)
         } else {
+            zone
+        }
+    }
+
+    pub fn to_local(
+        self,
+        viewer: Viewer,
+        zone: Visibility,
+        owner: Option<Side>,
+        ids: &BTreeMap<CardId, String>,
+    ) -> LocalCard {
+        let name = if viewer.sees(self.visibility(zone), owner) {
             Hidden::Unhidden(ids.get(&self.id).unwrap().clone())
+        } else {
+            Hidden::Hidden
         };
         LocalCard {
             name,
+            face_down: self.backside,
             counters: self.counters,
+            status: self.status,
             id: self.id,
+            token: false,
+            attached: self
+                .attached
+                .into_iter()
+                .map(|x| x.to_local(viewer, zone, owner, ids))
+                .collect(),
         }
     }
 }
 
+impl PlaceFrom {
+    /// Whose side of the table the place is on. None for places that belong to nobody.
+    pub fn side(self) -> Option<RelSide> {
+        match self {
+            PlaceFrom::Hand(_) => Some(RelSide::Same),
+            PlaceFrom::Space(side, _)
+            | PlaceFrom::Discard(side, _)
+            | PlaceFrom::Timeline(side, _)
+            | PlaceFrom::Deck(side, ..)
+            | PlaceFrom::Attached(side, ..) => Some(side),
+            PlaceFrom::Aside(_) | PlaceFrom::Floating(_) => None,
+        }
+    }
+}
+
+impl PlaceTo {
+    /// Whether this is somewhere on the table, the only places tokens can be.
+    pub fn is_board(&self) -> bool {
+        matches!(
+            self,
+            PlaceTo::Space(..)
+                | PlaceTo::Timeline(..)
+                | PlaceTo::Attached(..)
+                | PlaceTo::Floating(..)
+        )
+    }
+}
+
+/// Something that can have counters on it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum CounterTarget {
+    Card(PlaceFrom),
+    Player(RelSide),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TurnStep {
     Start,
@@ -295,12 +800,15 @@ impl TurnStep {
 pub struct Turn {
     pub whose: Side,
     pub step: TurnStep,
+    /// None unless the room plays with a clock.
+    pub clocks: Option<Clocks>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LocalTurn {
     pub whose: RelSide,
     pub step: TurnStep,
+    pub clocks: Option<LocalClocks>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -310,38 +818,288 @@ pub struct GameState {
     pub home_row: Row,
     pub away_row: Row,
     pub floating_cards: Vec<(Card, (usize, usize))>,
-    pub health: usize,
     pub aside: Vec<CardId>,
     pub turn: Turn,
+    pub result: Option<GameResult>,
+    pub draw_offer: Option<Side>,
+    /// Whether the game was dealt by [`ClientMsg::StartGame`] or a rematch.
+    pub started: bool,
+    /// Some while opening hands are being decided.
+    pub mulligan: Option<Mulligan>,
+    pub search_request: Option<SearchRequest>,
+    pub combat: Option<Combat>,
+    /// Who may act in a strict room. Goes back to whoever's turn it is when the turn changes.
+    pub priority: Side,
 }
 
 impl Default for GameState {
     fn default() -> Self {
+        Self::with_spaces(DEFAULT_SPACES)
+    }
+}
+
+impl GameState {
+    /// A fresh game whose rows have `spaces` spaces each.
+    pub fn with_spaces(spaces: usize) -> Self {
         Self {
             home_state: PlayerState::default(),
             away_state: PlayerState::default(),
-            home_row: Row::default(),
-            away_row: Row::default(),
+            home_row: Row::new(spaces),
+            away_row: Row::new(spaces),
             floating_cards: Vec::default(),
-            health: 20,
             aside: Vec::default(),
             turn: Turn {
                 whose: Side::Home,
                 step: TurnStep::Start,
+                clocks: None,
             },
+            result: None,
+            draw_offer: None,
+            started: false,
+            mulligan: None,
+            search_request: None,
+            combat: None,
+            priority: Side::Home,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameOverReason {
+    OutOfHealth,
+    OutOfTime,
+    Conceded,
+    AgreedDraw,
+}
+
+impl GameOverReason {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            GameOverReason::OutOfHealth => "Out of health",
+            GameOverReason::OutOfTime => "Out of time",
+            GameOverReason::Conceded => "Conceded",
+            GameOverReason::AgreedDraw => "Agreed to a draw",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// None for a draw
+    pub winner: Option<Side>,
+    pub reason: GameOverReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalGameResult {
+    pub winner: Option<RelSide>,
+    pub reason: GameOverReason,
+}
+
+impl GameResult {
+    pub fn relative_to(self, local: Side) -> LocalGameResult {
+        LocalGameResult {
+            winner: self.winner.map(|x| x.relative_to(local)),
+            reason: self.reason,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+/// How the best-of-N match played in the room is going.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct LocalMatchScore {
+    pub best_of: usize,
+    pub local_wins: usize,
+    pub distant_wins: usize,
+    pub draws: usize,
+    /// Set once someone won the majority of the games.
+    pub winner: Option<RelSide>,
+}
+
+impl LocalMatchScore {
+    pub fn games_played(&self) -> usize {
+        self.local_wins + self.distant_wins + self.draws
+    }
+    pub fn is_over(&self) -> bool {
+        self.winner.is_some() || self.games_played() >= self.best_of
+    }
+}
+
+pub const STARTING_HEALTH: usize = 20;
+
+/// Bookkeeping the room can do by itself at the start of a step of its owner's turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepAction {
+    Draw(DeckType),
+    GainBlood,
+    /// Removes a counter from every card in the player's row and timeline.
+    ClearCounter(String),
+    /// Takes a status off every card in the player's row and timeline.
+    ClearStatus(Status),
+}
+
+impl StepAction {
+    pub fn get_name(&self) -> String {
+        match self {
+            StepAction::Draw(DeckType::Main) => "draw from the main deck".to_owned(),
+            StepAction::Draw(DeckType::Blood) => "draw from the blood deck".to_owned(),
+            StepAction::GainBlood => "gain a blood".to_owned(),
+            StepAction::ClearCounter(counter) => format!("clear {counter} counters"),
+            StepAction::ClearStatus(status) => {
+                format!("clear {}", status.get_name().to_lowercase())
+            }
+        }
+    }
+}
+
+/// A step action a player can turn on or off for their own turns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepHook {
+    pub step: TurnStep,
+    pub action: StepAction,
+    pub enabled: bool,
+}
+
+impl StepHook {
+    /// The hooks everyone starts with. They're all off, so nothing happens until a player
+    /// asks for it.
+    pub fn defaults() -> Vec<StepHook> {
+        [
+            StepAction::Draw(DeckType::Main),
+            StepAction::GainBlood,
+            StepAction::ClearStatus(Status::Exhausted),
+            StepAction::ClearStatus(Status::SummoningSick),
+        ]
+        .into_iter()
+        .map(|action| StepHook {
+            step: TurnStep::Start,
+            action,
+            enabled: false,
+        })
+        .collect()
+    }
+}
+
+/// Rolling more dice than this at once is a typo.
+pub const MAX_DICE: u32 = 100;
+
+/// How games in a room are dealt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartRules {
+    pub main_cards: usize,
+    pub blood_cards: usize,
+    pub mulligan: MulliganRule,
+}
+
+impl Default for StartRules {
+    fn default() -> Self {
+        Self {
+            main_cards: 5,
+            blood_cards: 0,
+            mulligan: MulliganRule::OneFewer,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MulliganRule {
+    /// Games start as soon as the hands are dealt.
+    Disabled,
+    /// Every new hand is as big as the first one.
+    Free,
+    /// Every new hand has one main deck card less than the last one.
+    OneFewer,
+}
+
+impl MulliganRule {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            MulliganRule::Disabled => "No mulligans",
+            MulliganRule::Free => "Free mulligans",
+            MulliganRule::OneFewer => "One fewer card each time",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MulliganStatus {
+    pub mulligans: usize,
+    pub kept: bool,
+}
+
+/// Where both players are in the mulligan step, which goes on until both keep their hands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Mulligan {
+    pub home: MulliganStatus,
+    pub away: MulliganStatus,
+}
+
+impl Mulligan {
+    pub fn get(&self, side: Side) -> MulliganStatus {
+        match side {
+            Side::Home => self.home,
+            Side::Away => self.away,
+        }
+    }
+    pub fn get_mut(&mut self, side: Side) -> &mut MulliganStatus {
+        match side {
+            Side::Home => &mut self.home,
+            Side::Away => &mut self.away,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalMulligan {
+    pub local: MulliganStatus,
+    pub distant: MulliganStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PlayerState {
     pub hand: Vec<CardId>,
     pub main_deck: VecDeque<CardId>,
     pub blood_deck: VecDeque<CardId>,
     pub blood: usize,
+    pub health: usize,
     pub discard: Vec<CardId>,
     pub timeline: Vec<Card>,
-    pub searching: Option<DeckType>,
+    pub searching: Option<Search>,
+    pub looking_at: Option<LookAt>,
+    pub main_top: TopReveal,
+    pub blood_top: TopReveal,
+    /// Hand cards that were revealed, and to whom. Forgotten once they leave the hand.
+    pub revealed: Vec<(CardId, Visibility)>,
+    pub counters: HashMap<String, isize>,
+}
+
+/// Whether everyone gets to see the top card of a deck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TopReveal {
+    /// Revealed once. Only counts while this card is still on top.
+    pub card: Option<CardId>,
+    /// Playing with the top card revealed, whatever it is.
+    pub always: bool,
+}
+
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self {
+            hand: Vec::default(),
+            main_deck: VecDeque::default(),
+            blood_deck: VecDeque::default(),
+            blood: 0,
+            health: STARTING_HEALTH,
+            discard: Vec::default(),
+            timeline: Vec::default(),
+            searching: None,
+            looking_at: None,
+            main_top: TopReveal::default(),
+            blood_top: TopReveal::default(),
+            revealed: Vec::default(),
+            counters: HashMap::new(),
+        }
+    }
 }
 
 impl PlayerState {
@@ -357,9 +1115,98 @@ impl PlayerState {
             DeckType::Main => &mut self.main_deck,
         }
     }
+    /// Who may see a hand card that was revealed. None if it never was.
+    pub fn hand_visibility(&self, card: CardId) -> Option<Visibility> {
+        self.revealed
+            .iter()
+            .find(|(id, _)| *id == card)
+            .map(|(_, visibility)| *visibility)
+    }
+    /// Reveals a hand card. Revealing it again replaces who it was revealed to.
+    pub fn reveal(&mut self, card: CardId, visibility: Visibility) {
+        self.revealed.retain(|(id, _)| *id != card);
+        self.revealed.push((card, visibility));
+    }
+    pub fn get_top_reveal(&self, which: DeckType) -> TopReveal {
+        match which {
+            DeckType::Blood => self.blood_top,
+            DeckType::Main => self.main_top,
+        }
+    }
+    pub fn get_top_reveal_mut(&mut self, which: DeckType) -> &mut TopReveal {
+        match which {
+            DeckType::Blood => &mut self.blood_top,
+            DeckType::Main => &mut self.main_top,
+        }
+    }
+    /// Forgets a one-time reveal of the top card. Needed whenever the top card leaves, gets
+    /// covered or the deck is shuffled, so it isn't revealed again if it comes back.
+    pub fn hide_top(&mut self, which: DeckType) {
+        self.get_top_reveal_mut(which).card = None;
+    }
+    /// Takes the top card off a deck.
+    pub fn pop_top(&mut self, which: DeckType) -> Option<CardId> {
+        self.hide_top(which);
+        self.get_deck_mut(which).pop_back()
+    }
+    /// What everyone sees on top of a deck.
+    pub fn deck_top(&self, which: DeckType, ids: &BTreeMap<CardId, String>) -> LocalDeckTop {
+        let Some(top) = self.get_deck(which).back() else {
+            return LocalDeckTop::Empty;
+        };
+        let reveal = self.get_top_reveal(which);
+        if reveal.always || reveal.card == Some(*top) {
+            LocalDeckTop::Revealed(ids.get(top).unwrap().clone())
+        } else {
+            LocalDeckTop::Card
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// Who gets to see revealed cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RevealTarget {
+    Opponent,
+    /// The opponent and spectators.
+    Everyone,
+}
+
+impl RevealTarget {
+    pub fn visibility(self) -> Visibility {
+        match self {
+            RevealTarget::Opponent => Visibility::KnownTo(KnownTo {
+                home: true,
+                away: true,
+            }),
+            RevealTarget::Everyone => Visibility::Public,
+        }
+    }
+}
+
+/// A deck someone is searching. The searcher isn't always its owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Search {
+    pub owner: Side,
+    pub deck: DeckType,
+}
+
+/// Someone asking to search the other player's deck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchRequest {
+    pub by: Side,
+    pub search: Search,
+    /// Only the top this many cards, for [`ClientMsg::LookAtTop`].
+    pub top: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalSearchRequest {
+    pub by: RelSide,
+    pub deck: DeckType,
+    pub top: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DeckType {
     Blood,
     Main,
@@ -378,27 +1225,48 @@ impl Side {
             Side::Away => Side::Home,
         }
     }
+
+    pub fn relative_to(self, local: Side) -> RelSide {
+        if self == local {
+            RelSide::Same
+        } else {
+            RelSide::Other
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Card {
     pub id: CardId,
     pub backside: bool,
-    pub counters: HashMap<String, usize>,
+    pub known_to: KnownTo,
+    pub counters: HashMap<String, isize>,
+    pub status: StatusFlags,
+    /// Equipment, auras and the like. Only cards in a space have any, and they go wherever
+    /// their host goes as long as it stays in a space.
+    pub attached: Vec<Card>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LocalCard {
-    /// None if on the backside
+    /// Hidden if face down and not known to this player
     pub name: Hidden<String>,
+    /// Face-down cards can still have a name if this player knows them
+    pub face_down: bool,
     pub id: CardId,
-    pub counters: HashMap<String, usize>,
+    pub counters: HashMap<String, isize>,
+    pub status: StatusFlags,
+    /// Filled in by the server, which is the only one that knows.
+    pub token: bool,
+    pub attached: Vec<LocalCard>,
 }
 
 impl LocalCard {
     pub fn flipped(self, flipped: bool) -> Self {
-        let name = if flipped { Hidden::Hidden } else { self.name };
-        Self { name, ..self }
+        Self {
+            face_down: flipped,
+            ..self
+        }
     }
 }
 
@@ -413,13 +1281,24 @@ impl Card {
         Self {
             id,
             backside,
+            known_to: KnownTo::default(),
             counters: HashMap::new(),
+            status: StatusFlags::default(),
+            attached: vec![],
         }
     }
 
-    pub fn flipped(self, flipped: bool) -> Self {
+    /// Whoever turns a card face down gets to know what it is. A card that was already face
+    /// down stays known to the same players.
+    pub fn flipped(self, flipped: bool, by: Side) -> Self {
+        let known_to = match (self.backside, flipped) {
+            (true, true) => self.known_to,
+            (false, true) => KnownTo::only(by),
+            (_, false) => KnownTo::default(),
+        };
         Self {
             backside: flipped,
+            known_to,
             ..self
         }
     }
@@ -436,10 +1315,41 @@ pub enum LocalDeckTop {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LocalPlayer {
     pub blood: usize,
+    pub health: usize,
     pub discard: Vec<NamedCardId>,
     pub timeline: Vec<LocalCard>,
     pub main_deck_top: LocalDeckTop,
     pub blood_deck_top: LocalDeckTop,
+    /// Whether the player is playing with the top of their main deck revealed.
+    pub main_always_revealed: bool,
+    pub blood_always_revealed: bool,
+    pub hand_count: usize,
+    pub main_deck_size: usize,
+    pub blood_deck_size: usize,
+    /// The cards in this player's hand that were revealed and the viewer may see.
+    pub revealed_hand: Vec<NamedCardId>,
+    pub counters: HashMap<String, isize>,
+}
+
+impl LocalPlayer {
+    pub fn get_deck_top(&self, which: DeckType) -> &LocalDeckTop {
+        match which {
+            DeckType::Blood => &self.blood_deck_top,
+            DeckType::Main => &self.main_deck_top,
+        }
+    }
+    pub fn deck_size(&self, which: DeckType) -> usize {
+        match which {
+            DeckType::Blood => self.blood_deck_size,
+            DeckType::Main => self.main_deck_size,
+        }
+    }
+    pub fn always_revealed(&self, which: DeckType) -> bool {
+        match which {
+            DeckType::Blood => self.blood_always_revealed,
+            DeckType::Main => self.main_always_revealed,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -457,9 +1367,32 @@ pub struct LocalState {
     pub distant_row: LocalRow,
     pub hand: Vec<NamedCardId>,
     pub floating_cards: Vec<(LocalCard, (usize, usize))>,
-    pub health: usize,
     pub aside: Vec<NamedCardId>,
+    /// How many cards are aside, even when the viewer can't see them.
+    pub aside_count: usize,
     pub turn: LocalTurn,
+    pub result: Option<LocalGameResult>,
+    pub draw_offer: Option<RelSide>,
+    pub started: bool,
+    pub mulligan: Option<LocalMulligan>,
+    pub search_request: Option<LocalSearchRequest>,
+    pub combat: Option<LocalCombat>,
+    pub priority: RelSide,
+    /// Whether the room is strict. Filled in by the server, like the score.
+    pub strict: bool,
+    /// The room's clock settings. Filled in by the server.
+    pub clock_rules: Option<ClockRules>,
+    /// Filled in by the server, since the game state doesn't know about the match.
+    pub score: LocalMatchScore,
+    /// Filled in by the server, like the score.
+    pub start_rules: StartRules,
+    /// Start rules someone proposed and the other player hasn't accepted yet. Filled in by the
+    /// server.
+    pub proposed_start_rules: Option<(RelSide, StartRules)>,
+    /// Everything random or automatic that happened in the room so far. Filled in by the server.
+    pub history: Vec<LocalHistoryEntry>,
+    /// The viewer's step actions. Filled in by the server.
+    pub hooks: Vec<StepHook>,
 }
 
 impl LocalState {
@@ -490,7 +1423,9 @@ impl LocalState {
     pub fn pop_card(&mut self, from: PlaceFrom) -> Option<LocalCardOrNamedId> {
         match from {
             PlaceFrom::Hand(idx) => self.hand.find_remove(idx).map(Into::into),
-            PlaceFrom::Space(side, idx) => self.get_row_mut(side)[idx].take().map(Into::into),
+            PlaceFrom::Space(side, idx) => {
+                self.get_row_mut(side).slot_mut(idx)?.take().map(Into::into)
+            }
             PlaceFrom::Discard(side, idx) => self
                 .get_state_mut(side)
                 .discard
@@ -502,7 +1437,17 @@ impl LocalState {
                 .timeline
                 .find_remove(idx)
                 .map(Into::into),
-            PlaceFrom::Deck(side, deck_type, idx) => None,
+            PlaceFrom::Deck(..) => None,
+            PlaceFrom::Attached(side, space, idx) => self
+                .get_row_mut(side)
+                .get_mut(space)?
+                .attached
+                .find_remove(idx)
+                .map(Into::into),
+            PlaceFrom::Floating(idx) => {
+                let pos = self.floating_cards.iter().position(|(x, _)| x.id == idx)?;
+                Some(self.floating_cards.remove(pos).0.into())
+            }
         }
     }
     pub fn push_card(&mut self, card: LocalCardOrNamedId, to: PlaceTo) -> Option<()> {
@@ -510,13 +1455,19 @@ impl LocalState {
             PlaceTo::Hand => self.hand.push(card.into()),
             PlaceTo::Space(side, space, flipped) => {
                 let card: LocalCard = card.into();
-                self.get_row_mut(side)[space] = Some(card.flipped(flipped))
+                *self.get_row_mut(side).slot_mut(space)? = Some(card.flipped(flipped))
             }
             PlaceTo::Discard(side) => self.get_state_mut(side).discard.push(card.into()),
             PlaceTo::Aside => (),
             PlaceTo::Timeline(side) => self.get_state_mut(side).timeline.push(card.into()),
-            PlaceTo::Deck(deck_to, side, deck_type) => (),
+            PlaceTo::Deck(..) => (),
             PlaceTo::Liberate => (), // Do nothing. The card was removed earlier. Don't put it anywhere
+            PlaceTo::Attached(side, space) => self
+                .get_row_mut(side)
+                .get_mut(space)?
+                .attached
+                .push(card.into()),
+            PlaceTo::Floating(x, y) => self.floating_cards.push((card.into(), (x, y))),
         }
         Some(())
     }
@@ -553,8 +1504,12 @@ impl From<LocalCardOrNamedId> for LocalCard {
             LocalCardOrNamedId::Card(card) => card,
             LocalCardOrNamedId::Name(card) => LocalCard {
                 name: Hidden::Unhidden(card.name),
+                face_down: false,
                 counters: HashMap::new(),
+                status: StatusFlags::default(),
                 id: card.id,
+                token: false,
+                attached: vec![],
             },
         }
     }
@@ -582,12 +1537,14 @@ impl From<NamedCardId> for LocalCardOrNamedId {
 impl GameState {
     pub fn pop_card(&mut self, from: PlaceFrom, local_side: Side) -> Option<CardOrName> {
         match from {
-            PlaceFrom::Hand(idx) => self
-                .get_state_mut(local_side)
-                .hand
-                .find_remove(idx)
-                .map(Into::into),
-            PlaceFrom::Space(side, idx) => self.get_row_mut(side.make_real(local_side))[idx]
+            PlaceFrom::Hand(idx) => {
+                let player = self.get_state_mut(local_side);
+                player.revealed.retain(|(id, _)| *id != idx);
+                player.hand.find_remove(idx).map(Into::into)
+            }
+            PlaceFrom::Space(side, idx) => self
+                .get_row_mut(side.make_real(local_side))
+                .slot_mut(idx)?
                 .take()
                 .map(Into::into),
             PlaceFrom::Discard(side, idx) => self
@@ -604,14 +1561,27 @@ impl GameState {
             PlaceFrom::Deck(side, deck_type, idx) => {
                 let side = side.make_real(local_side);
                 let player = self.get_state_mut(side);
+                if player.get_top_reveal(deck_type).card == Some(idx) {
+                    player.hide_top(deck_type);
+                }
                 match deck_type {
                     DeckType::Blood => player.blood_deck.find_remove(idx).map(Into::into),
                     DeckType::Main => player.main_deck.find_remove(idx).map(Into::into),
                 }
             }
+            PlaceFrom::Attached(side, space, idx) => self
+                .get_row_mut(side.make_real(local_side))
+                .get_mut(space)?
+                .attached
+                .find_remove(idx)
+                .map(Into::into),
+            PlaceFrom::Floating(idx) => {
+                let pos = self.floating_cards.iter().position(|(x, _)| x.id == idx)?;
+                Some(self.floating_cards.remove(pos).0.into())
+            }
         }
     }
-    pub fn get_card(&self, from: PlaceFrom, local_side: Side) -> Option<CardOrNameRef> {
+    pub fn get_card(&self, from: PlaceFrom, local_side: Side) -> Option<CardOrNameRef<'_>> {
         match from {
             PlaceFrom::Hand(idx) => self.get_state(local_side).hand.find(idx).map(Into::into),
             PlaceFrom::Space(side, idx) => self
@@ -637,9 +1607,37 @@ impl GameState {
                     DeckType::Main => player.main_deck.find(idx).map(Into::into),
                 }
             }
+            PlaceFrom::Attached(side, space, idx) => self
+                .get_row(side.make_real(local_side))
+                .get(space)?
+                .attached
+                .find(idx)
+                .map(Into::into),
+            PlaceFrom::Floating(idx) => self
+                .floating_cards
+                .iter()
+                .find(|(x, _)| x.id == idx)
+                .map(|(x, _)| x.into()),
+        }
+    }
+    /// The counters on a card or a player. Named cards in decks and hands have none.
+    pub fn get_counters_mut(
+        &mut self,
+        target: CounterTarget,
+        local_side: Side,
+    ) -> Option<&mut HashMap<String, isize>> {
+        match target {
+            CounterTarget::Card(from) => match self.get_card_mut(from, local_side)? {
+                CardOrNameMut::Card(card) => Some(&mut card.counters),
+                CardOrNameMut::Name(_) => None,
+            },
+            CounterTarget::Player(side) => {
+                Some(&mut self.get_state_mut(side.make_real(local_side)).counters)
+            }
         }
     }
-    pub fn get_card_mut(&mut self, from: PlaceFrom, local_side: Side) -> Option<CardOrNameMut> {
+
+    pub fn get_card_mut(&mut self, from: PlaceFrom, local_side: Side) -> Option<CardOrNameMut<'_>> {
         match from {
             PlaceFrom::Hand(idx) => self
                 .get_state_mut(local_side)
@@ -669,6 +1667,17 @@ impl GameState {
                     DeckType::Main => player.main_deck.find_mut(idx).map(Into::into),
                 }
             }
+            PlaceFrom::Attached(side, space, idx) => self
+                .get_row_mut(side.make_real(local_side))
+                .get_mut(space)?
+                .attached
+                .find_mut(idx)
+                .map(Into::into),
+            PlaceFrom::Floating(idx) => self
+                .floating_cards
+                .iter_mut()
+                .find(|(x, _)| x.id == idx)
+                .map(|(x, _)| x.into()),
         }
     }
     pub fn push_card(&mut self, card: CardOrName, to: PlaceTo, local_side: Side) -> Option<()> {
@@ -676,7 +1685,9 @@ impl GameState {
             PlaceTo::Hand => self.get_state_mut(local_side).hand.push(card.into()),
             PlaceTo::Space(side, space, flipped) => {
                 let card: Card = card.into();
-                self.get_row_mut(side.make_real(local_side))[space] = Some(card.flipped(flipped))
+                *self
+                    .get_row_mut(side.make_real(local_side))
+                    .slot_mut(space)? = Some(card.flipped(flipped, local_side))
             }
             PlaceTo::Discard(side) => self
                 .get_state_mut(side.make_real(local_side))
@@ -690,6 +1701,9 @@ impl GameState {
             PlaceTo::Deck(deck_to, side, deck_type) => {
                 let side = side.make_real(local_side);
                 let player = self.get_state_mut(side);
+                if let DeckTo::Top = deck_to {
+                    player.hide_top(deck_type);
+                }
                 let deck = match deck_type {
                     DeckType::Blood => &mut player.blood_deck,
                     DeckType::Main => &mut player.main_deck,
@@ -700,6 +1714,12 @@ impl GameState {
                 }
             }
             PlaceTo::Liberate => (), // Do nothing. The card was removed earlier. Don't put it anywhere
+            PlaceTo::Attached(side, space) => self
+                .get_row_mut(side.make_real(local_side))
+                .get_mut(space)?
+                .attached
+                .push(card.into()),
+            PlaceTo::Floating(x, y) => self.floating_cards.push((card.into(), (x, y))),
         }
         Some(())
     }
@@ -709,48 +1729,197 @@ impl GameState {
         side: Option<Side>,
         ids: &BTreeMap<CardId, String>,
     ) -> LocalState {
-        let local_state = self.get_state(side.unwrap_or(Side::Home));
-        let away_state = self.get_state(side.unwrap_or(Side::Home).opposite());
-        let local_row = self.get_row(side.unwrap_or(Side::Home));
-        let away_row = self.get_row(side.unwrap_or(Side::Home).opposite());
-
-        let turn_side = if side.unwrap_or(Side::Home) == self.turn.whose {
+        // Spectators watch from Home's seat, but only get to see what's public.
+        let viewer = Viewer::from(side);
+        let perspective = side.unwrap_or(Side::Home);
+        let local_state = self.get_state(perspective);
+        let away_state = self.get_state(perspective.opposite());
+        let local_row = self.get_row(perspective);
+        let away_row = self.get_row(perspective.opposite());
+
+        let turn_side = if perspective == self.turn.whose {
             RelSide::Same
         } else {
             RelSide::Other
         };
 
         LocalState {
-            local_state: local_state.create_local(ids),
-            distant_state: away_state.create_local(ids),
-            local_row: local_row.clone().to_local(ids),
-            distant_row: away_row.clone().to_local(ids),
-            hand: local_state
-                .hand
-                .iter()
-                .map(|x| {
-                    let a = ids.get(x).unwrap().clone();
-                    NamedCardId { name: a, id: *x }
-                })
-                .collect(),
-            floating_cards: vec![],
-            health: self.health,
-            aside: self
-                .aside
+            local_state: local_state.create_local(viewer, perspective, ids),
+            distant_state: away_state.create_local(viewer, perspective.opposite(), ids),
+            local_row: local_row.clone().to_local(viewer, perspective, ids),
+            distant_row: away_row
                 .clone()
-                .into_iter()
-                .map(|x| NamedCardId {
-                    name: ids.get(&x).unwrap().clone(),
-                    id: x,
+                .to_local(viewer, perspective.opposite(), ids),
+            hand: named_visible(
+                &local_state.hand,
+                viewer,
+                self.visibility(perspective, Zone::Hand),
+                Some(perspective),
+                ids,
+            ),
+            floating_cards: self
+                .floating_cards
+                .iter()
+                .map(|(card, pos)| {
+                    let zone = Zone::Floating.visibility();
+                    (card.clone().to_local(viewer, zone, None, ids), *pos)
                 })
                 .collect(),
+            aside: named_visible(&self.aside, viewer, Zone::Aside.visibility(), None, ids),
+            aside_count: self.aside.len(),
             turn: LocalTurn {
                 whose: turn_side,
                 step: self.turn.step,
+                clocks: self
+                    .turn
+                    .clocks
+                    .map(|x| x.to_local(perspective, self.running_clock())),
             },
+            result: self.result.map(|result| result.relative_to(perspective)),
+            draw_offer: self.draw_offer.map(|x| x.relative_to(perspective)),
+            started: self.started,
+            mulligan: self.mulligan.map(|x| LocalMulligan {
+                local: x.get(perspective),
+                distant: x.get(perspective.opposite()),
+            }),
+            search_request: self.search_request.map(|x| LocalSearchRequest {
+                by: x.by.relative_to(perspective),
+                deck: x.search.deck,
+                top: x.top,
+            }),
+            combat: self.combat.as_ref().map(|x| x.to_local(perspective, self)),
+            priority: self.priority.relative_to(perspective),
+            strict: false,
+            clock_rules: None,
+            score: LocalMatchScore::default(),
+            start_rules: StartRules::default(),
+            proposed_start_rules: None,
+            history: vec![],
+            hooks: vec![],
+        }
+    }
+
+    /// Whose clock is ticking. Clocks only run once a game is dealt, and stop while opening hands
+    /// are decided and once the game is over.
+    pub fn running_clock(&self) -> Option<Side> {
+        if self.turn.clocks.is_none()
+            || !self.started
+            || self.mulligan.is_some()
+            || self.result.is_some()
+        {
+            return None;
+        }
+        Some(self.priority)
+    }
+
+    /// The result of the game if a player just ran out of health. None if nobody did, or if the
+    /// game was already over.
+    pub fn check_health(&self) -> Option<GameResult> {
+        if self.result.is_some() {
+            return None;
+        }
+        [Side::Home, Side::Away]
+            .into_iter()
+            .find(|side| self.get_state(*side).health == 0)
+            .map(|side| GameResult {
+                winner: Some(side.opposite()),
+                reason: GameOverReason::OutOfHealth,
+            })
+    }
+
+    /// Every card in a zone along with who may know it, from the top of decks and from the left
+    /// everywhere else. The aside and floating cards belong to nobody, so `side` doesn't
+    /// matter for them.
+    pub fn zone_cards(&self, side: Side, zone: Zone) -> Vec<(CardId, Visibility)> {
+        let state = self.get_state(side);
+        let visibility = self.visibility(side, zone);
+        let card = |x: &Card| (x.id, x.visibility(visibility));
+        match zone {
+            Zone::Hand => state
+                .hand
+                .iter()
+                .map(|x| (*x, state.hand_visibility(*x).unwrap_or(visibility)))
+                .collect(),
+            Zone::Deck(deck) => state
+                .get_deck(deck)
+                .iter()
+                .rev()
+                .map(|x| (*x, visibility))
+                .collect(),
+            Zone::Discard => state.discard.iter().map(|x| (*x, visibility)).collect(),
+            Zone::Timeline => state.timeline.iter().map(card).collect(),
+            Zone::Row => self.get_row(side).iter().map(card).collect(),
+            Zone::Aside => self.aside.iter().map(|x| (*x, visibility)).collect(),
+            Zone::Floating => self.floating_cards.iter().map(|(x, _)| card(x)).collect(),
         }
     }
 
+    /// Whether the player on `local_side` knows which card is at `from`. None if there's no
+    /// card there.
+    pub fn sees_card(&self, from: PlaceFrom, local_side: Side) -> Option<bool> {
+        let (side, zone, id) = match from {
+            PlaceFrom::Hand(id) => (local_side, Zone::Hand, id),
+            PlaceFrom::Space(side, space) => {
+                let side = side.make_real(local_side);
+                (side, Zone::Row, self.get_row(side).get(space)?.id)
+            }
+            PlaceFrom::Discard(side, id) => (side.make_real(local_side), Zone::Discard, id),
+            PlaceFrom::Aside(id) => (local_side, Zone::Aside, id),
+            PlaceFrom::Timeline(side, id) => (side.make_real(local_side), Zone::Timeline, id),
+            PlaceFrom::Deck(side, deck, id) => (side.make_real(local_side), Zone::Deck(deck), id),
+            PlaceFrom::Floating(id) => (local_side, Zone::Floating, id),
+            PlaceFrom::Attached(side, space, id) => {
+                let side = side.make_real(local_side);
+                let card = self.get_row(side).get(space)?.attached.find(id)?;
+                let visibility = card.visibility(self.visibility(side, Zone::Row));
+                return Some(Viewer::Player(local_side).sees(visibility, Some(side)));
+            }
+        };
+        let (_, visibility) = self
+            .zone_cards(side, zone)
+            .into_iter()
+            .find(|(x, _)| *x == id)?;
+        Some(Viewer::Player(local_side).sees(visibility, Some(side)))
+    }
+
+    /// Who may know the cards in one of `owner`'s zones. Decks are only known to whoever is
+    /// searching them, which can be either player.
+    pub fn visibility(&self, owner: Side, zone: Zone) -> Visibility {
+        let Zone::Deck(deck) = zone else {
+            return zone.visibility();
+        };
+        let search = Some(Search { owner, deck });
+        let mut known_to = KnownTo::default();
+        for side in [Side::Home, Side::Away] {
+            if self.get_state(side).searching == search {
+                known_to.insert(side);
+            }
+        }
+        if known_to == KnownTo::default() {
+            zone.visibility()
+        } else {
+            Visibility::KnownTo(known_to)
+        }
+    }
+
+    /// The contents of the deck the player is searching, if they are searching any.
+    pub fn create_search_for(
+        &self,
+        side: Side,
+        ids: &BTreeMap<CardId, String>,
+    ) -> Vec<NamedCardId> {
+        let Some(search) = self.get_state(side).searching else {
+            return vec![];
+        };
+        named_visible(
+            self.get_state(search.owner).get_deck(search.deck),
+            Viewer::Player(side),
+            self.visibility(search.owner, Zone::Deck(search.deck)),
+            Some(search.owner),
+            ids,
+        )
+    }
+
     pub fn get_row(&self, side: Side) -> &Row {
         match side {
             Side::Home => &self.home_row,
@@ -775,46 +1944,87 @@ impl GameState {
             Side::Away => &mut self.away_state,
         }
     }
+    /// Every card in a player's row and timeline.
+    pub fn board_mut(&mut self, side: Side) -> impl Iterator<Item = &mut Card> {
+        let (row, state) = match side {
+            Side::Home => (&mut self.home_row, &mut self.home_state),
+            Side::Away => (&mut self.away_row, &mut self.away_state),
+        };
+        row.iter_mut().chain(state.timeline.iter_mut())
+    }
 }
 
 impl PlayerState {
-    pub fn create_local(&self, ids: &BTreeMap<CardId, String>) -> LocalPlayer {
-        let main_deck_top = {
-            if self.main_deck.is_empty() {
-                LocalDeckTop::Empty
-            } else {
-                LocalDeckTop::Card
-            }
-        };
-        let blood_deck_top = {
-            if self.blood_deck.is_empty() {
-                LocalDeckTop::Empty
-            } else {
-                LocalDeckTop::Card
-            }
-        };
+    pub fn create_local(
+        &self,
+        viewer: Viewer,
+        owner: Side,
+        ids: &BTreeMap<CardId, String>,
+    ) -> LocalPlayer {
+        let main_deck_top = self.deck_top(DeckType::Main, ids);
+        let blood_deck_top = self.deck_top(DeckType::Blood, ids);
+        let timeline = Zone::Timeline.visibility();
         LocalPlayer {
             blood: self.blood,
-            discard: self
-                .discard
-                .iter()
-                .map(|id| NamedCardId {
-                    name: ids.get(id).unwrap().to_string(),
-                    id: *id,
-                })
-                .collect(),
+            health: self.health,
+            discard: named_visible(
+                &self.discard,
+                viewer,
+                Zone::Discard.visibility(),
+                Some(owner),
+                ids,
+            ),
             timeline: self
                 .timeline
                 .clone()
                 .into_iter()
-                .map(|x| x.to_local(ids))
+                .map(|x| x.to_local(viewer, timeline, Some(owner), ids))
                 .collect(),
             main_deck_top,
             blood_deck_top,
+            main_always_revealed: self.main_top.always,
+            blood_always_revealed: self.blood_top.always,
+            hand_count: self.hand.len(),
+            main_deck_size: self.main_deck.len(),
+            blood_deck_size: self.blood_deck.len(),
+            revealed_hand: self
+                .hand
+                .iter()
+                .filter(|id| {
+                    self.hand_visibility(**id)
+                        .is_some_and(|x| viewer.sees(x, Some(owner)))
+                })
+                .map(|id| NamedCardId {
+                    name: ids.get(id).unwrap().clone(),
+                    id: *id,
+                })
+                .collect(),
+            counters: self.counters.clone(),
         }
     }
 }
 
+/// Names every card in a zone if the viewer may see it. Zones listed by name alone have no way
+/// of hiding single cards, so the viewer gets either all of them or none.
+fn named_visible<'a>(
+    cards: impl IntoIterator<Item = &'a CardId>,
+    viewer: Viewer,
+    visibility: Visibility,
+    owner: Option<Side>,
+    ids: &BTreeMap<CardId, String>,
+) -> Vec<NamedCardId> {
+    if !viewer.sees(visibility, owner) {
+        return vec![];
+    }
+    cards
+        .into_iter()
+        .map(|id| NamedCardId {
+            name: ids.get(id).unwrap().clone(),
+            id: *id,
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RelSide {
     Same,
@@ -852,6 +2062,15 @@ pub enum CardOrNameMut<'a> {
     Name(&'a mut CardId),
 }
 
+impl CardOrName {
+    pub fn id(&self) -> CardId {
+        match self {
+            CardOrName::Card(card) => card.id,
+            CardOrName::Name(id) => *id,
+        }
+    }
+}
+
 impl From<CardOrName> for CardId {
     fn from(value: CardOrName) -> Self {
         match value {
@@ -864,11 +2083,7 @@ impl From<CardOrName> for Card {
     fn from(value: CardOrName) -> Card {
         match value {
             CardOrName::Card(card) => card,
-            CardOrName::Name(id) => Card {
-                id,
-                backside: false,
-                counters: HashMap::new(),
-            },
+            CardOrName::Name(id) => Card::from_id(id, false),
         }
     }
 }
@@ -888,22 +2103,12 @@ impl From<NamedCardId> for LocalCard {
     fn from(value: NamedCardId) -> Self {
         Self {
             name: Hidden::Unhidden(value.name),
+            face_down: false,
             counters: HashMap::new(),
+            status: StatusFlags::default(),
             id: value.id,
-        }
-    }
-}
-
-trait SafeRemove<T> {
-    fn safe_remove(&mut self, idx: usize) -> Option<T>;
-}
-
-impl<T> SafeRemove<T> for Vec<T> {
-    fn safe_remove(&mut self, idx: usize) -> Option<T> {
-        if idx < self.len() {
-            Some(self.remove(idx))
-        } else {
-            None
+            token: false,
+            attached: vec![],
         }
     }
 }
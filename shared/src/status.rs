@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Something that can be true of a card on the board for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    /// Used up for the turn, usually from attacking.
+    Exhausted,
+    /// Just arrived and can't attack yet.
+    SummoningSick,
+    /// A generic marker for whatever the players need to keep track of.
+    Marked,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Exhausted, Status::SummoningSick, Status::Marked];
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Status::Exhausted => "Exhausted",
+            Status::SummoningSick => "Summoning sick",
+            Status::Marked => "Marked",
+        }
+    }
+}
+
+/// The statuses a card has. They're all public, even on face-down cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StatusFlags {
+    pub exhausted: bool,
+    pub summoning_sick: bool,
+    pub marked: bool,
+}
+
+impl StatusFlags {
+    pub fn get(self, status: Status) -> bool {
+        match status {
+            Status::Exhausted => self.exhausted,
+            Status::SummoningSick => self.summoning_sick,
+            Status::Marked => self.marked,
+        }
+    }
+    pub fn set(&mut self, status: Status, value: bool) {
+        match status {
+            Status::Exhausted => self.exhausted = value,
+            Status::SummoningSick => self.summoning_sick = value,
+            Status::Marked => self.marked = value,
+        }
+    }
+}
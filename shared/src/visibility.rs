@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{DeckType, Side};
+
+/// Whoever a [`crate::LocalState`] is being built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Viewer {
+    Player(Side),
+    Spectator,
+}
+
+impl From<Option<Side>> for Viewer {
+    fn from(value: Option<Side>) -> Self {
+        match value {
+            Some(side) => Viewer::Player(side),
+            None => Viewer::Spectator,
+        }
+    }
+}
+
+/// Who gets to know the name of a card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    Public,
+    OwnerOnly,
+    /// Face-down cards and searched decks. Spectators never get to see these.
+    KnownTo(KnownTo),
+    Hidden,
+}
+
+/// Every place a card can be in. Each one has a default visibility, which cards and
+/// players can then restrict further.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Zone {
+    Hand,
+    Deck(DeckType),
+    Discard,
+    Timeline,
+    Row,
+    Aside,
+    Floating,
+}
+
+impl Zone {
+    pub fn visibility(self) -> Visibility {
+        match self {
+            Zone::Hand => Visibility::OwnerOnly,
+            Zone::Deck(_) => Visibility::Hidden,
+            Zone::Discard => Visibility::Public,
+            Zone::Timeline => Visibility::Public,
+            Zone::Row => Visibility::Public,
+            Zone::Aside => Visibility::Public,
+            Zone::Floating => Visibility::Public,
+        }
+    }
+}
+
+impl Viewer {
+    /// `owner` is None for zones that don't belong to either player, like the aside.
+    pub fn sees(self, visibility: Visibility, owner: Option<Side>) -> bool {
+        match visibility {
+            Visibility::Public => true,
+            Visibility::OwnerOnly => owner.is_some_and(|owner| self == Viewer::Player(owner)),
+            Visibility::KnownTo(known_to) => match self {
+                Viewer::Player(side) => known_to.contains(side),
+                Viewer::Spectator => false,
+            },
+            Visibility::Hidden => false,
+        }
+    }
+}
+
+/// The players who know what a face-down card is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct KnownTo {
+    pub home: bool,
+    pub away: bool,
+}
+
+impl KnownTo {
+    pub fn only(side: Side) -> Self {
+        let mut known_to = Self::default();
+        known_to.insert(side);
+        known_to
+    }
+    pub fn contains(self, side: Side) -> bool {
+        match side {
+            Side::Home => self.home,
+            Side::Away => self.away,
+        }
+    }
+    pub fn insert(&mut self, side: Side) {
+        match side {
+            Side::Home => self.home = true,
+            Side::Away => self.away = true,
+        }
+    }
+}
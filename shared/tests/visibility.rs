@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+
+use shared::{Card, CardId, DeckType, GameState, KnownTo, Search, Side, Space, Viewer, Visibility};
+
+const HOME: Viewer = Viewer::Player(Side::Home);
+const AWAY: Viewer = Viewer::Player(Side::Away);
+const SPECTATOR: Viewer = Viewer::Spectator;
+const EVERYONE: &[Viewer] = &[HOME, AWAY, SPECTATOR];
+const NOBODY: &[Viewer] = &[];
+const PLAYERS: &[Viewer] = &[HOME, AWAY];
+
+/// A game with one uniquely named card in every zone, along with who should know each name.
+struct Fixture {
+    state: GameState,
+    ids: BTreeMap<CardId, String>,
+    expected: Vec<(String, &'static [Viewer])>,
+}
+
+impl Fixture {
+    fn card(&mut self, name: &str, known_to: &'static [Viewer]) -> CardId {
+        let id = CardId(self.ids.len());
+        self.ids.insert(id, name.to_owned());
+        self.expected.push((name.to_owned(), known_to));
+        id
+    }
+
+    fn new() -> Self {
+        let mut fixture = Fixture {
+            state: GameState::default(),
+            ids: BTreeMap::new(),
+            expected: vec![],
+        };
+
+        for (side, prefix, owner) in [(Side::Home, "home", &[HOME]), (Side::Away, "away", &[AWAY])]
+        {
+            let hand = fixture.card(&format!("{prefix} hand"), owner);
+            let hand_revealed = fixture.card(&format!("{prefix} hand revealed"), PLAYERS);
+            let main = fixture.card(&format!("{prefix} main deck"), NOBODY);
+            let blood = fixture.card(&format!("{prefix} blood deck"), NOBODY);
+            let discard = fixture.card(&format!("{prefix} discard"), EVERYONE);
+            let timeline = fixture.card(&format!("{prefix} timeline"), EVERYONE);
+            let timeline_down = fixture.card(&format!("{prefix} timeline face down"), NOBODY);
+            let row = fixture.card(&format!("{prefix} row"), EVERYONE);
+            let row_down = fixture.card(&format!("{prefix} row face down"), NOBODY);
+            let row_known = fixture.card(&format!("{prefix} row known face down"), owner);
+            let attached = fixture.card(&format!("{prefix} attached"), EVERYONE);
+            let attached_down = fixture.card(&format!("{prefix} attached face down"), NOBODY);
+
+            let player = fixture.state.get_state_mut(side);
+            player.hand.push(hand);
+            player.hand.push(hand_revealed);
+            let both = KnownTo {
+                home: true,
+                away: true,
+            };
+            player
+                .revealed
+                .push((hand_revealed, Visibility::KnownTo(both)));
+            player.main_deck.push_back(main);
+            player.blood_deck.push_back(blood);
+            player.discard.push(discard);
+            player.timeline.push(Card::from_id(timeline, false));
+            player.timeline.push(Card::from_id(timeline_down, true));
+
+            let row_state = fixture.state.get_row_mut(side);
+            row_state[Space(0)] = Some(Card {
+                attached: vec![
+                    Card::from_id(attached, false),
+                    Card::from_id(attached_down, true),
+                ],
+                ..Card::from_id(row, false)
+            });
+            row_state[Space(1)] = Some(Card::from_id(row_down, true));
+            row_state[Space(2)] = Some(Card {
+                known_to: KnownTo::only(side),
+                ..Card::from_id(row_known, true)
+            });
+        }
+
+        let aside = fixture.card("aside", EVERYONE);
+        fixture.state.aside.push(aside);
+        let floating = fixture.card("floating", EVERYONE);
+        let floating_down = fixture.card("floating face down", NOBODY);
+        fixture.state.floating_cards = vec![
+            (Card::from_id(floating, false), (0, 0)),
+            (Card::from_id(floating_down, true), (10, 10)),
+        ];
+
+        fixture
+    }
+
+    fn local_json(&self, viewer: Viewer) -> String {
+        let side = match viewer {
+            Viewer::Player(side) => Some(side),
+            Viewer::Spectator => None,
+        };
+        serde_json::to_string(&self.state.create_local_for(side, &self.ids)).unwrap()
+    }
+}
+
+fn leaks(json: &str, name: &str) -> bool {
+    json.contains(&format!("\"{name}\""))
+}
+
+#[test]
+fn every_card_is_seen_by_exactly_who_should_see_it() {
+    let fixture = Fixture::new();
+
+    for viewer in [HOME, AWAY, SPECTATOR] {
+        let json = fixture.local_json(viewer);
+        for (name, known_to) in &fixture.expected {
+            assert_eq!(
+                leaks(&json, name),
+                known_to.contains(&viewer),
+                "{viewer:?} and {name:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn searching_never_changes_the_local_state() {
+    let mut fixture = Fixture::new();
+    fixture.state.home_state.searching = Some(Search {
+        owner: Side::Home,
+        deck: DeckType::Main,
+    });
+    fixture.state.away_state.searching = Some(Search {
+        owner: Side::Home,
+        deck: DeckType::Blood,
+    });
+
+    for viewer in [HOME, AWAY, SPECTATOR] {
+        let json = fixture.local_json(viewer);
+        for (name, known_to) in &fixture.expected {
+            assert_eq!(leaks(&json, name), known_to.contains(&viewer));
+        }
+    }
+}
+
+#[test]
+fn search_shows_only_the_searched_deck() {
+    let mut fixture = Fixture::new();
+
+    for deck in [DeckType::Main, DeckType::Blood] {
+        fixture.state.home_state.searching = Some(Search {
+            owner: Side::Home,
+            deck,
+        });
+        let names: Vec<String> = fixture
+            .state
+            .create_search_for(Side::Home, &fixture.ids)
+            .into_iter()
+            .map(|x| x.name)
+            .collect();
+
+        let expected = match deck {
+            DeckType::Main => "home main deck",
+            DeckType::Blood => "home blood deck",
+        };
+        assert_eq!(names, vec![expected.to_owned()]);
+        assert!(
+            fixture
+                .state
+                .create_search_for(Side::Away, &fixture.ids)
+                .is_empty()
+        );
+    }
+}
+
+#[test]
+fn searching_the_other_deck_shows_only_that_deck() {
+    let mut fixture = Fixture::new();
+    fixture.state.away_state.searching = Some(Search {
+        owner: Side::Home,
+        deck: DeckType::Blood,
+    });
+
+    let names: Vec<String> = fixture
+        .state
+        .create_search_for(Side::Away, &fixture.ids)
+        .into_iter()
+        .map(|x| x.name)
+        .collect();
+    assert_eq!(names, ["home blood deck"]);
+    assert!(
+        fixture
+            .state
+            .create_search_for(Side::Home, &fixture.ids)
+            .is_empty()
+    );
+}
+
+#[test]
+fn nothing_to_search_without_searching() {
+    let fixture = Fixture::new();
+
+    for side in [Side::Home, Side::Away] {
+        assert!(
+            fixture
+                .state
+                .create_search_for(side, &fixture.ids)
+                .is_empty()
+        );
+    }
+}
+
+#[test]
+fn revealed_deck_tops_are_seen_by_everyone() {
+    let mut fixture = Fixture::new();
+    let top = *fixture.state.home_state.main_deck.back().unwrap();
+    fixture.state.home_state.main_top.card = Some(top);
+    fixture.state.away_state.blood_top.always = true;
+
+    for viewer in [HOME, AWAY, SPECTATOR] {
+        let json = fixture.local_json(viewer);
+        assert!(leaks(&json, "home main deck"));
+        assert!(leaks(&json, "away blood deck"));
+        assert!(!leaks(&json, "home blood deck"));
+        assert!(!leaks(&json, "away main deck"));
+    }
+}
+
+#[test]
+fn a_revealed_top_is_hidden_again_once_it_leaves() {
+    let mut fixture = Fixture::new();
+    let top = *fixture.state.home_state.main_deck.back().unwrap();
+    fixture.state.home_state.main_top.card = Some(top);
+    let under = fixture.card("home card under the top", NOBODY);
+    fixture.state.home_state.main_deck.push_front(under);
+
+    fixture.state.home_state.main_deck.pop_back();
+
+    for viewer in [HOME, AWAY, SPECTATOR] {
+        let json = fixture.local_json(viewer);
+        assert!(!leaks(&json, "home card under the top"));
+    }
+}